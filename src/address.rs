@@ -0,0 +1,42 @@
+use crate::{MAX_ADDR_BYTES, MIN_ADDR_WIDTH};
+
+/// An RX/TX address whose length is checked at compile time.
+///
+/// Constructing an `Address<N>` with `N` outside of
+/// `MIN_ADDR_WIDTH..=MAX_ADDR_BYTES` fails to compile. Remember to
+/// configure a matching width with `set_address_width(N)`.
+///
+/// ```compile_fail
+/// let _ = embedded_nrf24l01::Address::new([0xA5, 0xA5]);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Address<const N: usize>([u8; N]);
+
+impl<const N: usize> Address<N> {
+    const VALID_LEN: () = assert!(
+        N >= MIN_ADDR_WIDTH && N <= MAX_ADDR_BYTES,
+        "address length out of range"
+    );
+
+    /// Wrap the address bytes
+    pub const fn new(bytes: [u8; N]) -> Self {
+        let () = Self::VALID_LEN;
+        Address(bytes)
+    }
+
+    /// Address width in bytes
+    pub const fn width(&self) -> u8 {
+        N as u8
+    }
+
+    /// Obtain a slice of the address
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for Address<N> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
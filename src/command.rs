@@ -2,6 +2,19 @@ pub use crate::payload::Payload;
 use crate::registers::Register;
 use core::marker::PhantomData;
 
+/// `R_REGISTER` opcode, `0b000A_AAAA`
+pub const R_REGISTER: u8 = 0b0000_0000;
+/// `W_REGISTER` opcode, `0b001A_AAAA`
+pub const W_REGISTER: u8 = 0b0010_0000;
+
+/// Combines a register opcode with a 5-bit register address
+///
+/// The address is masked so that it can never spill into the opcode bits.
+pub fn register_command(opcode_base: u8, addr: u8) -> u8 {
+    debug_assert!(addr <= 0x1F, "register address {:#x} out of range", addr);
+    opcode_base | (addr & 0x1F)
+}
+
 pub trait Command {
     fn len(&self) -> usize;
     fn encode(&self, data: &mut [u8]);
@@ -28,7 +41,7 @@ impl<R: Register> Command for ReadRegister<R> {
     }
 
     fn encode(&self, buf: &mut [u8]) {
-        buf[0] = R::addr();
+        buf[0] = register_command(R_REGISTER, R::addr());
     }
 
     type Response = R;
@@ -53,7 +66,7 @@ impl<R: Register> Command for WriteRegister<R> {
     }
 
     fn encode(&self, buf: &mut [u8]) {
-        buf[0] = 0b10_0000 | R::addr();
+        buf[0] = register_command(W_REGISTER, R::addr());
         self.register.encode(&mut buf[1..]);
     }
 
@@ -110,6 +123,31 @@ impl<'a> Command for WriteTxPayload<'a> {
     fn decode_response(_: &[u8]) -> Self::Response {}
 }
 
+pub struct WriteAckPayload<'a> {
+    pipe: u8,
+    data: &'a [u8],
+}
+
+impl<'a> WriteAckPayload<'a> {
+    pub fn new(pipe: u8, data: &'a [u8]) -> Self {
+        WriteAckPayload { pipe, data }
+    }
+}
+
+impl<'a> Command for WriteAckPayload<'a> {
+    fn len(&self) -> usize {
+        1 + self.data.len()
+    }
+
+    fn encode(&self, buf: &mut [u8]) {
+        buf[0] = 0b1010_1000 | (self.pipe & 0b111);
+        buf[1..].copy_from_slice(self.data);
+    }
+
+    type Response = ();
+    fn decode_response(_: &[u8]) -> Self::Response {}
+}
+
 pub struct ReadRxPayloadWidth;
 
 impl Command for ReadRxPayloadWidth {
@@ -1,11 +1,15 @@
 //! Configuration Parameters for the NRF24L01+ Board
 
-use crate::PIPES_COUNT;
+use core::fmt;
+use core::fmt::Debug;
+
+use crate::registers::{RfSetup, Status};
+use crate::{Address, Error, Mode, MAX_ADDR_BYTES, MAX_PAYLOAD_BYTES, MIN_ADDR_BYTES, MIN_ADDR_WIDTH, PIPES_COUNT};
 
 /// Supported air data rates.
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub enum DataRate {
-    /// 250 Kbps
+    /// 250 Kbps (nRF24L01+ only)
     R250Kbps,
     /// 1 Mbps
     #[default]
@@ -14,6 +18,20 @@ pub enum DataRate {
     R2Mbps,
 }
 
+impl DataRate {
+    /// Decodes the `RF_DR_LOW`/`RF_DR_HIGH` bits of `RF_SETUP`
+    ///
+    /// Both bits set is reserved and returns `Error::InvalidDataRate` instead of guessing.
+    pub fn from_register<SPIE: Debug>(rf_setup: &RfSetup) -> Result<DataRate, Error<SPIE>> {
+        match (rf_setup.rf_dr_low(), rf_setup.rf_dr_high()) {
+            (true, false) => Ok(DataRate::R250Kbps),
+            (false, false) => Ok(DataRate::R1Mbps),
+            (false, true) => Ok(DataRate::R2Mbps),
+            (true, true) => Err(Error::InvalidDataRate),
+        }
+    }
+}
+
 /// Supported CRC modes
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum CrcMode {
@@ -38,17 +56,201 @@ pub enum PALevel {
     PA18dBm,
 }
 
+impl PALevel {
+    /// Decodes the `RF_PWR` bits of `RF_SETUP`
+    pub fn from_register(rf_setup: &RfSetup) -> PALevel {
+        match rf_setup.rf_pwr() {
+            3 => PALevel::PA0dBm,
+            2 => PALevel::PA6dBm,
+            1 => PALevel::PA12dBm,
+            _ => PALevel::PA18dBm,
+        }
+    }
+}
+
+impl fmt::Display for DataRate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DataRate::R250Kbps => "250 kbps",
+            DataRate::R1Mbps => "1 Mbps",
+            DataRate::R2Mbps => "2 Mbps",
+        })
+    }
+}
+
+impl fmt::Display for CrcMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            CrcMode::Disabled => "CRC off",
+            CrcMode::OneByte => "CRC 8-bit",
+            CrcMode::TwoBytes => "CRC 16-bit",
+        })
+    }
+}
+
+impl fmt::Display for PALevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            PALevel::PA0dBm => "0 dBm",
+            PALevel::PA6dBm => "-6 dBm",
+            PALevel::PA12dBm => "-12 dBm",
+            PALevel::PA18dBm => "-18 dBm",
+        })
+    }
+}
+
+/// One of the six RX data pipes
+///
+/// Unlike a plain `usize`, a `Pipe` is always valid, so the methods taking one can't panic
+/// on an out-of-range pipe number.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Pipe {
+    /// Pipe 0, also receives the ACKs for transmissions
+    P0,
+    /// Pipe 1
+    P1,
+    /// Pipe 2, shares all but the LSB of its address with pipe 1
+    P2,
+    /// Pipe 3, shares all but the LSB of its address with pipe 1
+    P3,
+    /// Pipe 4, shares all but the LSB of its address with pipe 1
+    P4,
+    /// Pipe 5, shares all but the LSB of its address with pipe 1
+    P5,
+}
+
+impl Pipe {
+    /// All pipes in order
+    pub const ALL: [Pipe; PIPES_COUNT] = [Pipe::P0, Pipe::P1, Pipe::P2, Pipe::P3, Pipe::P4, Pipe::P5];
+
+    /// Pipe number
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl From<Pipe> for usize {
+    fn from(pipe: Pipe) -> usize {
+        pipe.index()
+    }
+}
+
+impl core::convert::TryFrom<usize> for Pipe {
+    type Error = ();
+
+    fn try_from(pipe_no: usize) -> Result<Self, ()> {
+        Pipe::ALL.get(pipe_no).copied().ok_or(())
+    }
+}
+
+impl core::convert::TryFrom<u8> for Pipe {
+    type Error = ();
+
+    /// Converts a pipe number as reported by the chip, e.g. by
+    /// [`read_with_pipe()`](crate::Rx::read_with_pipe)
+    fn try_from(pipe_no: u8) -> Result<Self, ()> {
+        Pipe::try_from(usize::from(pipe_no))
+    }
+}
+
 /// Interrupt Masks grouped together into a single struct
+///
+/// These are the `MASK_*` bits of `CONFIG`: `true` *masks* the interrupt, so the event no
+/// longer pulls IRQ low. [`enable_interrupts()`](trait.NRF24L01Configuration.html#tymethod.enable_interrupts)
+/// takes the opposite, more natural view.
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct InterruptMask {
-    /// Trip Interrupt when data is available to be read
+    /// Don't trip the interrupt when data is available to be read
     pub data_ready_rx: bool,
-    /// Trip Interrupt when data has been sent
+    /// Don't trip the interrupt when data has been sent
     pub data_sent_tx: bool,
-    /// Trip interrupt when the maximum retries has been hit for a transmission
+    /// Don't trip the interrupt when the maximum retries has been hit for a transmission
     pub max_retramsits_tx: bool,
 }
 
+/// One of the three interrupt sources
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum InterruptKind {
+    /// Data received (`RX_DR`)
+    RxDataReady,
+    /// Data sent (`TX_DS`)
+    TxDataSent,
+    /// Maximum number of retransmits reached (`MAX_RT`)
+    MaxRetransmit,
+}
+
+/// Interrupt flags that were set, as returned by
+/// [`take_interrupts()`](../struct.NRF24L01.html#method.take_interrupts)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct InterruptStatus {
+    /// Data was received (`RX_DR`)
+    pub data_ready_rx: bool,
+    /// Data was sent (`TX_DS`)
+    pub data_sent_tx: bool,
+    /// The maximum number of retransmits was reached (`MAX_RT`)
+    pub max_retransmits_tx: bool,
+}
+
+impl From<Status> for InterruptStatus {
+    fn from(status: Status) -> Self {
+        InterruptStatus {
+            data_ready_rx: status.rx_dr(),
+            data_sent_tx: status.tx_ds(),
+            max_retransmits_tx: status.max_rt(),
+        }
+    }
+}
+
+/// Decodes the interrupt flags of a raw `STATUS` byte
+///
+/// For code that got hold of the byte elsewhere, e.g. an interrupt handler without access
+/// to the driver.
+pub fn decode_status(status: u8) -> InterruptStatus {
+    InterruptStatus::from(Status::from(status))
+}
+
+/// All flags of `FIFO_STATUS`, as returned by
+/// [`fifo_status()`](../struct.NRF24L01.html#method.fifo_status)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FifoSummary {
+    /// RX FIFO is empty
+    pub rx_empty: bool,
+    /// RX FIFO is full
+    pub rx_full: bool,
+    /// TX FIFO is empty
+    pub tx_empty: bool,
+    /// TX FIFO is full
+    pub tx_full: bool,
+    /// The last TX payload is being reused
+    pub tx_reuse: bool,
+}
+
+/// Inconsistencies found by [`NRF24L01Config::validate()`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ConfigError {
+    /// Auto-ack is enabled on a pipe while CRC is disabled
+    AutoAckWithoutCrc,
+    /// A transmitter with auto-ack on pipe 0 needs `RX_ADDR_P0` to equal `TX_ADDR` to
+    /// receive the ACKs
+    AckAddressMismatch,
+    /// The address width is outside of `MIN_ADDR_WIDTH..=MAX_ADDR_BYTES`
+    InvalidAddressWidth(u8),
+    /// The address of a pipe doesn't have the configured width (pipes 0 and 1) or is
+    /// empty (pipes 2-5)
+    RxAddressLength(usize),
+    /// The TX address doesn't have the configured width
+    TxAddressLength,
+    /// The RF channel is beyond 125
+    InvalidChannel(u8),
+    /// The static payload length of a pipe is outside of `1..=32`
+    InvalidPayloadLength {
+        /// The pipe the length was given for
+        pipe: usize,
+        /// The offending length
+        len: u8,
+    },
+}
+
 /// Retransmit Configuration grouped together into a single struct
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct RetransmitConfig {
@@ -58,6 +260,39 @@ pub struct RetransmitConfig {
     pub count: u8,
 }
 
+/// The per-pipe part of a configuration, for switching roles with
+/// [`apply_pipe_profile()`](trait.NRF24L01Configuration.html#tymethod.apply_pipe_profile)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct PipeProfile<'a> {
+    /// The pipes that receive
+    pub read_enabled_pipes: [bool; PIPES_COUNT],
+    /// The address of each pipe, pipes 2-5 only use the first byte
+    pub rx_addrs: [&'a [u8]; PIPES_COUNT],
+    /// The pipes that send auto acknowledgements
+    pub auto_ack_pipes: [bool; PIPES_COUNT],
+    /// Static payload length of each pipe, `None` for dynamic payloads
+    pub pipe_payload_lengths: [Option<u8>; PIPES_COUNT],
+}
+
+/// Finds the first invalid static payload length, along with its pipe
+///
+/// Static lengths must be within `1..=32`. A pipe that doesn't receive may also have a
+/// length of 0, the reset value of `RX_PW_Px`, which is what an unused pipe reads back as.
+pub(crate) fn invalid_payload_length(
+    lengths: &[Option<u8>; PIPES_COUNT],
+    read_enabled_pipes: &[bool; PIPES_COUNT],
+) -> Option<(usize, u8)> {
+    lengths
+        .iter()
+        .zip(read_enabled_pipes)
+        .enumerate()
+        .find_map(|(pipe, (len, enabled))| match *len {
+            Some(0) if !enabled => None,
+            Some(len) if !(1..=MAX_PAYLOAD_BYTES as u8).contains(&len) => Some((pipe, len)),
+            _ => None,
+        })
+}
+
 /// A software struct organizing the configuration of the NRF24L01.  I might end up
 /// changing this because it is technically possible for the hardware to change and
 /// not allert the software
@@ -87,12 +322,74 @@ pub struct NRF24L01Config<'a> {
     pub address_width: u8,
     /// The length of data to expect from each pipe
     pub pipe_payload_lengths: [Option<u8>; PIPES_COUNT],
+    /// The role of the device (`Tx` or `Rx`), the mode
+    /// [`configure()`](trait.NRF24L01Configuration.html#tymethod.configure) leaves it in
+    pub mode: Mode,
 }
 
 impl<'a> NRF24L01Config<'a> {
+    /// The default configuration, usable in `const` contexts
+    ///
+    /// The addresses and their width of 5 bytes match the reset values of the chip.
+    pub const DEFAULT: Self = Self {
+        data_rate: DataRate::R1Mbps,
+        crc_mode: CrcMode::Disabled,
+        rf_channel: 0u8,
+        pa_level: PALevel::PA18dBm,
+        interrupt_mask: InterruptMask { data_ready_rx: false, data_sent_tx: false, max_retramsits_tx: false },
+        read_enabled_pipes: [false; PIPES_COUNT],
+        rx_addrs: [&[0xE7; 5], &[0xC2; 5], &[0xC3], &[0xC4], &[0xC5], &[0xC6]],
+        tx_addr: &[0xE7; 5],
+        retransmit_config: RetransmitConfig { delay: 0u8, count: 0u8 },
+        auto_ack_pipes: [false; PIPES_COUNT],
+        address_width: 5u8,
+        pipe_payload_lengths: [None; PIPES_COUNT],
+        mode: Mode::Standby,
+    };
+
+    /// Checks the rules that span several fields
+    ///
+    /// Each rule violated has its own [`ConfigError`]. A transmitter (`mode` is [`Mode::Tx`])
+    /// with auto-ack on pipe 0 receives its ACKs there, so `RX_ADDR_P0` must equal
+    /// `TX_ADDR`. `EN_DPL` isn't part of the configuration, it is enabled whenever a pipe
+    /// has a dynamic payload length.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let any_auto_ack = self.auto_ack_pipes.iter().any(|enabled| *enabled);
+        if any_auto_ack && self.crc_mode == CrcMode::Disabled {
+            return Err(ConfigError::AutoAckWithoutCrc);
+        }
+        if self.mode == Mode::Tx && self.auto_ack_pipes[0] && self.rx_addrs[0] != self.tx_addr {
+            return Err(ConfigError::AckAddressMismatch);
+        }
+
+        if !(MIN_ADDR_WIDTH..=MAX_ADDR_BYTES).contains(&usize::from(self.address_width)) {
+            return Err(ConfigError::InvalidAddressWidth(self.address_width));
+        }
+        let width = usize::from(self.address_width);
+        for (pipe, addr) in self.rx_addrs.iter().enumerate() {
+            let valid = if pipe < 2 { addr.len() == width } else { !addr.is_empty() };
+            if !valid {
+                return Err(ConfigError::RxAddressLength(pipe));
+            }
+        }
+        if self.tx_addr.len() != width {
+            return Err(ConfigError::TxAddressLength);
+        }
+
+        if self.rf_channel > 125 {
+            return Err(ConfigError::InvalidChannel(self.rf_channel));
+        }
+        if let Some((pipe, len)) = invalid_payload_length(&self.pipe_payload_lengths, &self.read_enabled_pipes) {
+            return Err(ConfigError::InvalidPayloadLength { pipe, len });
+        }
+        Ok(())
+    }
+
     /// Creates a new instance of NRF24L01Config with given parameters
+    ///
+    /// This is a `const fn`, so a configuration can be defined as a `const` or `static`.
     #[allow(clippy::too_many_arguments)]
-    pub fn new(
+    pub const fn new(
         data_rate: DataRate,
         crc_mode: CrcMode,
         rf_channel: u8,
@@ -105,6 +402,7 @@ impl<'a> NRF24L01Config<'a> {
         auto_ack_pipes: [bool; PIPES_COUNT],
         address_width: u8,
         pipe_payload_lengths: [Option<u8>; PIPES_COUNT],
+        mode: Mode,
     ) -> Self {
         Self {
             data_rate,
@@ -119,30 +417,116 @@ impl<'a> NRF24L01Config<'a> {
             auto_ack_pipes,
             address_width,
             pipe_payload_lengths,
+            mode,
+        }
+    }
+}
+
+/// A configuration owning its addresses, as read back from a device
+///
+/// Obtained with [`export_config()`](trait.NRF24L01Configuration.html#tymethod.export_config)
+/// and applied with [`import_config()`](trait.NRF24L01Configuration.html#tymethod.import_config),
+/// e.g. to clone the settings of a reference unit.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct NRF24L01ConfigOwned {
+    /// The rate to send data at
+    pub data_rate: DataRate,
+    /// The crc bit correction mode
+    pub crc_mode: CrcMode,
+    /// The RF channel for this device to listen on
+    pub rf_channel: u8,
+    /// The power amplifier level
+    pub pa_level: PALevel,
+    /// The interrupt mask
+    pub interrupt_mask: InterruptMask,
+    /// The pipes that are to be read from
+    pub read_enabled_pipes: [bool; PIPES_COUNT],
+    /// The addresses to read from (per pipe), only the first `address_width` bytes are used,
+    /// and only the first byte for pipes 2-5
+    pub rx_addrs: [[u8; MAX_ADDR_BYTES]; PIPES_COUNT],
+    /// The address to transmit to, only the first `address_width` bytes are used
+    pub tx_addr: [u8; MAX_ADDR_BYTES],
+    /// At what delay and how many times should data be retransmitted
+    pub retransmit_config: RetransmitConfig,
+    /// Should we sent an auto acknowledgement to data received at these pipes
+    pub auto_ack_pipes: [bool; PIPES_COUNT],
+    /// the address width for enhanced shockburst (3-5 bytes)
+    pub address_width: u8,
+    /// The length of data to expect from each pipe
+    pub pipe_payload_lengths: [Option<u8>; PIPES_COUNT],
+    /// The role of the device (`Tx` or `Rx`), the mode
+    /// [`configure()`](trait.NRF24L01Configuration.html#tymethod.configure) leaves it in
+    pub mode: Mode,
+}
+
+impl NRF24L01ConfigOwned {
+    /// Borrows this as a [`NRF24L01Config`]
+    pub fn as_config(&self) -> NRF24L01Config<'_> {
+        let width = usize::from(self.address_width).clamp(MIN_ADDR_BYTES, MAX_ADDR_BYTES);
+        let mut rx_addrs: [&[u8]; PIPES_COUNT] = [&[]; PIPES_COUNT];
+        for (pipe_no, addr) in self.rx_addrs.iter().enumerate() {
+            rx_addrs[pipe_no] = if pipe_no < 2 { &addr[..width] } else { &addr[..1] };
+        }
+        NRF24L01Config {
+            data_rate: self.data_rate,
+            crc_mode: self.crc_mode,
+            rf_channel: self.rf_channel,
+            pa_level: self.pa_level,
+            interrupt_mask: self.interrupt_mask,
+            read_enabled_pipes: self.read_enabled_pipes,
+            rx_addrs,
+            tx_addr: &self.tx_addr[..width],
+            retransmit_config: self.retransmit_config,
+            auto_ack_pipes: self.auto_ack_pipes,
+            address_width: self.address_width,
+            pipe_payload_lengths: self.pipe_payload_lengths,
+            mode: self.mode,
+        }
+    }
+}
+
+impl<'a> From<&NRF24L01Config<'a>> for NRF24L01ConfigOwned {
+    fn from(config: &NRF24L01Config<'a>) -> Self {
+        fn copy_addr(addr: &[u8]) -> [u8; MAX_ADDR_BYTES] {
+            let mut owned = [0; MAX_ADDR_BYTES];
+            let len = addr.len().min(MAX_ADDR_BYTES);
+            owned[..len].copy_from_slice(&addr[..len]);
+            owned
+        }
+
+        let mut rx_addrs = [[0; MAX_ADDR_BYTES]; PIPES_COUNT];
+        for (owned, addr) in rx_addrs.iter_mut().zip(config.rx_addrs.iter()) {
+            *owned = copy_addr(addr);
+        }
+        NRF24L01ConfigOwned {
+            data_rate: config.data_rate,
+            crc_mode: config.crc_mode,
+            rf_channel: config.rf_channel,
+            pa_level: config.pa_level,
+            interrupt_mask: config.interrupt_mask,
+            read_enabled_pipes: config.read_enabled_pipes,
+            rx_addrs,
+            tx_addr: copy_addr(config.tx_addr),
+            retransmit_config: config.retransmit_config,
+            auto_ack_pipes: config.auto_ack_pipes,
+            address_width: config.address_width,
+            pipe_payload_lengths: config.pipe_payload_lengths,
+            mode: config.mode,
         }
     }
 }
 
 impl<'a> Default for NRF24L01Config<'a> {
     fn default() -> Self {
-        Self {
-            data_rate: DataRate::R1Mbps,
-            crc_mode: CrcMode::Disabled,
-            rf_channel: 0u8,
-            pa_level: PALevel::PA18dBm,
-            interrupt_mask: InterruptMask { data_ready_rx: false, data_sent_tx: false, max_retramsits_tx: false },
-            read_enabled_pipes: [false; PIPES_COUNT],
-            rx_addrs: [b"rx"; PIPES_COUNT],
-            tx_addr: b"tx",
-            retransmit_config: RetransmitConfig { delay: 0u8, count: 0u8 },
-            auto_ack_pipes: [false; PIPES_COUNT],
-            address_width: 3u8,
-            pipe_payload_lengths: [None; PIPES_COUNT],
-        }
+        Self::DEFAULT
     }
 }
 
 /// Trait for a device to implement to modify the various aspects of the NRF24L01 Configuration
+///
+/// Setters that touch registers which may only be changed in Standby or Power Down
+/// (`RF_SETUP`, `RF_CH`, `SETUP_AW`, `SETUP_RETR`, `EN_AA`, `EN_RXADDR` and the
+/// addresses) drop to Standby for the write and restore the previous mode afterwards.
 pub trait NRF24L01Configuration<'a> {
     /// The error type to return on unsuccessful operation (most likely SPI error)
     type Error;
@@ -159,39 +543,282 @@ pub trait NRF24L01Configuration<'a> {
     fn set_rf_channel(&mut self, rf_channel: u8) -> Result<(), Self::Error>;
 
     /// Sets the data rate to transmit data
+    ///
+    /// Reads back `RF_SETUP` so the power amplifier level on the chip is kept. For 250 kbps
+    /// `RF_SETUP` is read once more to check that `RF_DR_LOW` stuck; if it didn't, the
+    /// previous data rate is restored and `Error::UnsupportedDataRate` is returned.
     fn set_data_rate(&mut self, rate: DataRate) -> Result<(), Self::Error>;
 
+    /// Sets the data rate like [`set_data_rate()`](#tymethod.set_data_rate), refusing channels
+    /// that don't fit the bandwidth
+    ///
+    /// At 2 Mbps the signal occupies 2 MHz instead of 1 MHz, so neighbouring channels overlap.
+    /// Keeping 2 Mbps links on even channels at least 2 MHz away from the band edges
+    /// (`2..=123`) avoids this; other channels return `Error::ChannelBandwidthConflict`.
+    fn set_data_rate_checked(&mut self, rate: DataRate) -> Result<(), Self::Error>;
+
     /// Sets the power amplifier level
+    ///
+    /// Reads back `RF_SETUP` so the data rate on the chip is kept.
     fn set_pa_level(&mut self, power: PALevel) -> Result<(), Self::Error>;
 
+    /// Sets the data rate and power amplifier level in a single `RF_SETUP` write
+    ///
+    /// Reads back `RF_SETUP` so the other bits, such as `LNA_HCURR`, are kept.
+    fn set_rf_setup(&mut self, rate: DataRate, power: PALevel) -> Result<(), Self::Error>;
+
+    /// Sets the low noise amplifier gain (`LNA_HCURR` in `RF_SETUP`)
+    ///
+    /// High gain improves receive sensitivity at the cost of current draw. Only the original
+    /// nRF24L01 has this bit; on the nRF24L01+ it is reserved, so with the `nrf24l01-plus`
+    /// feature this returns `Error::UnsupportedOnVariant` without touching the register.
+    fn set_lna_gain(&mut self, high: bool) -> Result<(), Self::Error>;
+
     /// Sets the bit correction mode
+    ///
+    /// Enhanced ShockBurst requires CRC, so disabling it while auto-ack is enabled on any pipe
+    /// is refused with `Error::CrcRequiredForAutoAck`.
     fn set_crc_mode(&mut self, mode: CrcMode) -> Result<(), Self::Error>;
 
     /// Sets the interrupt mask
+    ///
+    /// Mind the polarity: a `true` field masks (disables) that interrupt. See
+    /// [`enable_interrupts()`](#tymethod.enable_interrupts) for the inverse.
     fn set_interrupt_mask(&mut self, interrupt_mask: InterruptMask) -> Result<(), Self::Error>;
 
+    /// Chooses which events pull IRQ low, `true` meaning the interrupt fires
+    ///
+    /// The inverse of [`set_interrupt_mask()`](#tymethod.set_interrupt_mask), the mask bits
+    /// are set for the events passed as `false`.
+    fn enable_interrupts(&mut self, rx: bool, tx: bool, max_rt: bool) -> Result<(), Self::Error>;
+
+    /// Masks or unmasks a single interrupt, leaving the other two as they are
+    fn mask_interrupt(&mut self, which: InterruptKind, masked: bool) -> Result<(), Self::Error>;
+
     /// Sets the pipes that are read-enabled
+    ///
+    /// A pipe needs a payload length first, enabling one whose cached static width is 0
+    /// returns `Error::InvalidPayloadLength` without writing anything.
     fn set_read_enabled_pipes(&mut self, read_enabled_pipes: &[bool; PIPES_COUNT]) -> Result<(), Self::Error>;
 
     /// Sets the read address of a specific pipe
+    ///
+    /// Pipes 2-5 only have a single address byte of their own and share the rest with pipe 1,
+    /// so for them only the first byte of `addr` is used. That is the LSB, as addresses are
+    /// written LSB first.
+    ///
+    /// An address outside of `MIN_ADDR_BYTES..=MAX_ADDR_BYTES` bytes (`1..=MAX_ADDR_BYTES`
+    /// for pipes 2-5) returns `Error::InvalidAddressLength` without writing anything.
+    ///
+    /// Panics for pipes beyond 5, use [`set_pipe_rx_addr()`](#tymethod.set_pipe_rx_addr)
+    /// to rule that out at compile time.
     fn set_rx_addrs(&mut self, pipe_no: usize, addr: &'a [u8]) -> Result<(), Self::Error>;
 
     /// Sets the address to send data to
+    ///
+    /// An address outside of `MIN_ADDR_BYTES..=MAX_ADDR_BYTES` bytes returns
+    /// `Error::InvalidAddressLength` without writing anything.
     fn set_tx_addr(&mut self, addr: &'a [u8]) -> Result<(), Self::Error>;
 
+    /// Dedicates pipe 0 to receiving ACKs on a PTX
+    ///
+    /// When enabled, `TX_ADDR` is mirrored into `RX_ADDR_P0`, now and on every later
+    /// [`set_tx_addr()`](#tymethod.set_tx_addr), so the ACKs of the receiver are heard. The
+    /// previous pipe 0 address is remembered and restored when disabled.
+    ///
+    /// The chip receives ACKs on pipe 0 only, so while this is enabled pipe 0 cannot also
+    /// serve as a regular RX pipe with an address of its own.
+    fn use_pipe0_for_ack(&mut self, enable: bool) -> Result<(), Self::Error>;
+
+    /// Sets the address to read from on a [`Pipe`]
+    ///
+    /// Same as [`set_rx_addrs()`](#tymethod.set_rx_addrs), without the possibility of an
+    /// invalid pipe number.
+    fn set_pipe_rx_addr(&mut self, pipe: Pipe, addr: &'a [u8]) -> Result<(), Self::Error>;
+
+    /// Sets the single address byte (the LSB) of one of the pipes 2-5
+    ///
+    /// The remaining address bytes are shared with pipe 1. Unlike the full addresses, `lsb`
+    /// is copied into the cache, so it can be computed at runtime (e.g. a node ID). Pipes 0
+    /// and 1 need a full address, for them `Error::InvalidAddressLength` is returned.
+    fn set_rx_addr_lsb(&mut self, pipe: Pipe, lsb: u8) -> Result<(), Self::Error>;
+
+    /// Sets the read address of a specific pipe from a length-checked [`Address`]
+    fn set_rx_address<const N: usize>(&mut self, pipe_no: usize, addr: &'a Address<N>) -> Result<(), Self::Error>;
+
+    /// Sets the address to send data to from a length-checked [`Address`]
+    fn set_tx_address<const N: usize>(&mut self, addr: &'a Address<N>) -> Result<(), Self::Error>;
+
     /// Sets the delay and number of retransmissions for failed transmissions
     fn set_retransmit_config(&mut self, delay: u8, count: u8) -> Result<(), Self::Error>;
 
+    /// Sets `count` retransmissions with the shortest delay that fits the ACK
+    ///
+    /// The delay must cover the receiver switching to TX (130µs) plus the air time of the
+    /// ACK packet:
+    ///
+    /// ```text
+    /// bits  = 8 * (1 + address_width + ack_payload + crc_bytes) + 9
+    /// delay = 130µs + bits / data_rate
+    /// ```
+    ///
+    /// rounded up to the 250µs steps of `ARD`, and at least 500µs at 250 Kbps. If ACK
+    /// payloads are enabled in `FEATURE`, a full 32-byte ACK payload is assumed. That gives
+    /// 250µs for plain ACKs at 1 and 2 Mbps, 500µs at 250 Kbps and up to 1500µs with ACK
+    /// payloads at 250 Kbps.
+    fn set_auto_retransmit_delay_auto(&mut self, count: u8) -> Result<(), Self::Error>;
+
     /// Sets which pipes should automatically send an ack message
+    ///
+    /// Note that the hardware forces CRC on while any pipe has auto-ack enabled, regardless
+    /// of the configured CRC mode.
     fn set_auto_ack(&mut self, auto_ack_pipes: [bool; PIPES_COUNT]) -> Result<(), Self::Error>;
 
+    /// Switches to plain ShockBurst, for talking to radios without Enhanced ShockBurst
+    ///
+    /// Disables auto-ack on all pipes, auto-retransmit, CRC and dynamic payloads (including
+    /// ACK payloads). Every pipe falls back to the static width in its `RX_PW_Px` register,
+    /// set it with [`set_pipes_payload_lengths()`](#tymethod.set_pipes_payload_lengths).
+    ///
+    /// The peer must match: no CRC (or its check disabled), no ACKs expected, the same
+    /// static payload length, address width, data rate and channel.
+    fn set_legacy_shockburst(&mut self) -> Result<(), Self::Error>;
+
+    /// Enables or disables auto-ack on a single pipe, leaving the others as they are
+    fn set_pipe_auto_ack(&mut self, pipe: Pipe, enabled: bool) -> Result<(), Self::Error>;
+
+    /// Configures fire-and-forget broadcasting
+    ///
+    /// When enabled, retransmits are turned off (`ARC = 0`, `ARD = 0`) and auto-ack is disabled
+    /// on all pipes, so nothing waits for ACKs that never come. The prior retransmit and
+    /// auto-ack configuration is remembered and restored when disabled again.
+    fn set_broadcast_mode(&mut self, enabled: bool) -> Result<(), Self::Error>;
+
+    /// Trims the configuration for low-power deployments, based on the cached configuration
+    ///
+    /// Only the pipes marked in the read-enabled pipes stay enabled, auto-ack is turned off on
+    /// all other pipes, and the interrupts of events that cannot occur (`RX_DR` without any
+    /// enabled pipe, `MAX_RT` without auto-ack on pipe 0) are masked.
+    fn minimize_power(&mut self) -> Result<(), Self::Error>;
+
+    /// Sets up the device as a primary transmitter (PTX) in one go
+    ///
+    /// Sets the TX address (and the address width to its length) and mirrors it into pipe 0
+    /// so ACKs are received, enables pipe 0 with auto-ack, enables CRC (two bytes) if it was
+    /// disabled, retransmits up to 15 times with a delay of 1500μs, applies the cached
+    /// payload lengths and enters Standby.
+    fn configure_as_transmitter(&mut self, tx_addr: &'a [u8], data_rate: DataRate, pa: PALevel, channel: u8) -> Result<(), Self::Error>;
+
+    /// Sets up the device as a primary receiver (PRX) in one go
+    ///
+    /// Listens on `rx_addr` on pipe 1 with auto-ack (setting the address width to its
+    /// length), enables CRC (two bytes) if it was disabled, applies the cached payload
+    /// lengths and enters Standby. Reading switches to RX mode.
+    fn configure_as_receiver(&mut self, rx_addr: &'a [u8], data_rate: DataRate, pa: PALevel, channel: u8) -> Result<(), Self::Error>;
+
     /// Sets the width of the address for outgoing and incoming transmissions (between 3 and 5 bytes)
+    ///
+    /// Other widths return `Error::InvalidConfig(ConfigError::InvalidAddressWidth)` without
+    /// writing anything.
     fn set_address_width(&mut self, width: u8) -> Result<(), Self::Error>;
 
     /// Sets the expected payload length for each of the rx pipes (defaults to None = dynamic payload length)
+    ///
+    /// Static lengths must be within `1..=32`, otherwise nothing is written and
+    /// `Error::InvalidPayloadLength` is returned. In particular `Some(0)` is refused for a
+    /// pipe that is read-enabled: a pipe without a fixed width is `None`, which enables
+    /// dynamic payloads on it. Pipes that don't receive may keep the width 0 they reset to.
     fn set_pipes_payload_lengths(&mut self, lengths: [Option<u8>; PIPES_COUNT]) -> Result<(), Self::Error>;
 
+    /// Reads the configuration back from the device registers
+    ///
+    /// Unlike [`get_config()`](#tymethod.get_config) this doesn't rely on the cache, so it
+    /// reflects what the chip is actually configured to. A pipe with a static width of 0
+    /// receives nothing, so it is reported as not read-enabled, which keeps the result
+    /// [valid](struct.NRF24L01Config.html#method.validate) for
+    /// [`configure()`](#tymethod.configure) even on a freshly reset chip. The `mode` is the
+    /// one the device is currently in.
+    fn export_config(&mut self) -> Result<NRF24L01ConfigOwned, Self::Error>;
+
+    /// Writes every setting of `cfg` to the device, regardless of the cache
+    ///
+    /// The cache refers to the addresses in `cfg`, so it must outlive the driver.
+    fn import_config(&mut self, cfg: &'a NRF24L01ConfigOwned) -> Result<(), Self::Error>;
+
+    /// Sets up the whole radio from `cfg`, typically a `static` profile applied at boot
+    ///
+    /// `cfg` is [validated](struct.NRF24L01Config.html#method.validate) first, an
+    /// inconsistent one returns `Error::InvalidConfig` without writing anything. Then every
+    /// register is written in Standby, as with [`import_config()`](#tymethod.import_config),
+    /// and the device switches to the `mode` of the configuration.
+    fn configure(&mut self, cfg: &'a NRF24L01ConfigOwned) -> Result<(), Self::Error>;
+
+    /// Switches a single pipe between dynamic and static payload length
+    ///
+    /// Unlike [`set_pipes_payload_lengths()`](#tymethod.set_pipes_payload_lengths) this
+    /// leaves the static widths (`RX_PW_Px`) alone. Enabling enables dynamic payloads in
+    /// `FEATURE` as well. When disabling, the pipe falls back to the static width already in
+    /// its `RX_PW_Px` register, which is cached (0 means the pipe is unused).
+    fn set_pipe_dynamic_payload(&mut self, pipe: Pipe, dynamic: bool) -> Result<(), Self::Error>;
+
+    /// Enables dynamic payloads on the given pipes, disabling them on the others
+    ///
+    /// Sets `FEATURE.EN_DPL` along with the `DYNPD` bits, both are needed. Pipes left out
+    /// fall back to the static width in their `RX_PW_Px` register, as with
+    /// [`set_pipe_dynamic_payload()`](#tymethod.set_pipe_dynamic_payload).
+    fn enable_dynamic_payloads(&mut self, pipes: [bool; PIPES_COUNT]) -> Result<(), Self::Error>;
+
+    /// Disables dynamic payloads on all pipes, clearing `DYNPD` and `FEATURE.EN_DPL`
+    ///
+    /// ACK payloads depend on dynamic payloads, so `FEATURE.EN_ACK_PAY` is cleared as well.
+    /// Every pipe falls back to the static width in its `RX_PW_Px` register.
+    fn disable_dynamic_payloads(&mut self) -> Result<(), Self::Error>;
+
+    /// Enables or disables ACK payloads (`FEATURE.EN_ACK_PAY`)
+    ///
+    /// ACK payloads need dynamic payloads, so enabling them while `FEATURE.EN_DPL` is clear
+    /// returns `Error::DynamicPayloadRequired` without writing anything.
+    fn set_ack_payload_enabled(&mut self, enabled: bool) -> Result<(), Self::Error>;
+
+    /// Reads whether ACK payloads are enabled (`FEATURE.EN_ACK_PAY`)
+    fn is_ack_payload_enabled(&mut self) -> Result<bool, Self::Error>;
+
+    /// Fully sets up a pipe for reception
+    ///
+    /// Writes the address, the payload length (`None` for dynamic) and auto-ack for `pipe`,
+    /// then enables it in `EN_RXADDR`. A static length outside of `1..=32` is rejected
+    /// before anything is written.
+    fn enable_rx_pipe(&mut self, pipe: Pipe, addr: &'a [u8], payload: Option<u8>, auto_ack: bool) -> Result<(), Self::Error>;
+
+    /// Stops reception on a pipe, disabling it in `EN_RXADDR` and `EN_AA`
+    fn disable_rx_pipe(&mut self, pipe: Pipe) -> Result<(), Self::Error>;
+
+    /// Sets up the addresses for a two-way link with a single peer
+    ///
+    /// Sends to `peer_addr` (`TX_ADDR`), receives the ACKs from the peer on pipe 0
+    /// (`RX_ADDR_P0 = peer_addr`) and the peer's packets on pipe 1 (`RX_ADDR_P1 = my_addr`),
+    /// and enables pipes 0 and 1. The peer makes the mirror call, with the two addresses
+    /// swapped.
+    ///
+    /// Addresses are given in the byte order they are written to the chip, which sends them
+    /// over the air LSB first: `addr[0]` is the LSB. As long as both sides pass the same
+    /// slices this needs no further thought, but it matters when talking to a library that
+    /// takes addresses as integers.
+    fn set_bidirectional_addrs(&mut self, my_addr: &'a [u8], peer_addr: &'a [u8]) -> Result<(), Self::Error>;
+
+    /// Switches all pipes to `profile` in one go
+    ///
+    /// Addresses and static payload lengths are checked first, an invalid one returns
+    /// `Error::InvalidAddressLength` or `Error::InvalidPayloadLength` without writing
+    /// anything. Reception is disabled while the pipes are rewritten. If an SPI error
+    /// occurs midway, the previous pipe setup is restored on a best-effort basis before the
+    /// error is returned; should that fail as well, the radio may be left partly configured.
+    fn apply_pipe_profile(&mut self, profile: &PipeProfile<'a>) -> Result<(), Self::Error>;
+
     /// Sets all of the fields of the nrf configuration
+    ///
+    /// The configuration is [validated](struct.NRF24L01Config.html#method.validate) first,
+    /// an inconsistent one returns `Error::InvalidConfig` without writing anything.
     fn set_nrf_configuration(&mut self, configuration: NRF24L01Config<'a>) -> Result<(), Self::Error>;
 
     /// Gets the data transmission rate
@@ -206,6 +833,13 @@ pub trait NRF24L01Configuration<'a> {
     /// Gets the radio's power amplification level
     fn get_pa_level(&self) -> PALevel;
 
+    /// Reads the data rate and power amplification level from the live `RF_SETUP` register
+    ///
+    /// Unlike [`get_data_rate()`](#tymethod.get_data_rate) and
+    /// [`get_pa_level()`](#tymethod.get_pa_level) this doesn't trust the cached
+    /// configuration. The reserved data rate combination returns `Error::InvalidDataRate`.
+    fn read_rf_setup(&mut self) -> Result<(DataRate, PALevel), Self::Error>;
+
     /// Gets the interrupt mask for the radio
     fn get_interrupt_mask(&self) -> InterruptMask;
 
@@ -213,7 +847,7 @@ pub trait NRF24L01Configuration<'a> {
     fn get_read_enabled_pipes(&self) -> [bool; PIPES_COUNT];
 
     /// Gets the rx addresses of each pipe
-    fn get_rx_addrs(&self) -> [&'a [u8]; PIPES_COUNT];
+    fn get_rx_addrs(&self) -> [&[u8]; PIPES_COUNT];
 
     /// Gets the tx address
     fn get_tx_addr(&self) -> &'a [u8];
@@ -231,5 +865,11 @@ pub trait NRF24L01Configuration<'a> {
     fn get_pipe_payload_lengths(&self) -> [Option<u8>; PIPES_COUNT];
 
     /// Gets the full NRF24L01 configuraiton
-    fn get_config(&self) -> NRF24L01Config;
+    fn get_config(&self) -> NRF24L01Config<'_>;
+
+    /// Gets a copy of the full NRF24L01 configuration that owns its addresses
+    ///
+    /// Like [`get_config()`](#tymethod.get_config) this returns the cached configuration,
+    /// but the result doesn't borrow anything and can be stored freely.
+    fn snapshot_config(&self) -> NRF24L01ConfigOwned;
 }
\ No newline at end of file
@@ -0,0 +1,71 @@
+use crate::mode::ChangeModes;
+use crate::rx::{ReceivedPacket, Rx};
+use crate::tx::Tx;
+
+/// Two radios used together for pseudo full duplex, `A` dedicated to TX and `B` to RX
+///
+/// Both may sit on the same SPI bus with separate CSN lines, e.g. through a bus sharing
+/// crate. Every driver call pulls its CSN low only for the duration of a single SPI
+/// transaction and releases it before returning, and as `DualRadio` only ever calls into
+/// one radio at a time, the two CSN lines are never active together.
+pub struct DualRadio<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> DualRadio<A, B> {
+    /// Take ownership of the transmitting radio `a` and the receiving radio `b`
+    pub fn new(a: A, b: B) -> Self {
+        DualRadio { a, b }
+    }
+
+    /// Access the transmitting radio
+    pub fn a(&mut self) -> &mut A {
+        &mut self.a
+    }
+
+    /// Access the receiving radio
+    pub fn b(&mut self) -> &mut B {
+        &mut self.b
+    }
+
+    /// Give back both radios
+    pub fn release(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+impl<A, B, E> DualRadio<A, B>
+where
+    A: Tx<Error = E> + ChangeModes<Error = E>,
+    B: Rx<Error = E> + ChangeModes<Error = E>,
+{
+    /// Puts `a` into TX mode and `b` into RX mode
+    pub fn start(&mut self) -> Result<(), E> {
+        self.a.to_tx()?;
+        self.b.to_rx()
+    }
+
+    /// Send `packet` on `a`, handing every packet `b` receives meanwhile to `on_receive`
+    ///
+    /// Returns once `a` is done, with whether the packet was acknowledged (always `true`
+    /// without auto-ack). Packets arriving on `b` after that stay in its RX FIFO.
+    pub fn transmit_on_a_receive_on_b(
+        &mut self,
+        packet: &[u8],
+        mut on_receive: impl FnMut(ReceivedPacket),
+    ) -> Result<bool, E> {
+        self.a.send(packet)?;
+        loop {
+            while let Some(received) = self.b.receive()? {
+                on_receive(received);
+            }
+
+            match self.a.poll_send() {
+                Ok(success) => return Ok(success),
+                Err(nb::Error::WouldBlock) => {}
+                Err(nb::Error::Other(err)) => return Err(err),
+            }
+        }
+    }
+}
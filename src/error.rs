@@ -1,5 +1,7 @@
 use core::fmt::Debug;
 
+use crate::config::ConfigError;
+
 /// Wraps an SPI error
 ///
 /// TODO: eliminate this?
@@ -9,6 +11,58 @@ pub enum Error<SPIE: Debug> {
     SpiError(SPIE),
     /// Module not connected
     NotConnected,
+    /// Every byte read back was `0x00`: MISO is stuck low or not connected
+    BusStuckLow,
+    /// Every byte read back was `0xFF`: MISO is floating or stuck high
+    BusStuckHigh,
+    /// The SPI transfer returned fewer bytes than the command needs
+    ShortTransfer {
+        /// Length of the command and its response
+        expected: usize,
+        /// Length actually transferred
+        got: usize,
+    },
+    /// An address is shorter than `MIN_ADDR_BYTES` (1 byte for pipes 2-5) or longer than
+    /// `MAX_ADDR_BYTES`
+    InvalidAddressLength {
+        /// The length of the address given
+        got: usize,
+    },
+    /// Static payload length for a pipe is outside of `1..=32`
+    InvalidPayloadLength {
+        /// The pipe the length was given for
+        pipe: usize,
+        /// The offending length
+        len: u8,
+    },
+    /// CRC cannot be disabled while auto-ack is enabled on any pipe
+    CrcRequiredForAutoAck,
+    /// The current RF channel is unsuitable for the 2 MHz bandwidth of 2 Mbps
+    ChannelBandwidthConflict,
+    /// `RF_SETUP` holds the reserved data rate combination (both `RF_DR` bits set)
+    InvalidDataRate,
+    /// ACK payloads cannot be enabled without dynamic payloads (`FEATURE.EN_DPL`)
+    DynamicPayloadRequired,
+    /// ACK payloads (`FEATURE.EN_ACK_PAY`) are not enabled
+    AckPayloadRequired,
+    /// The chip didn't accept 250 kbps, a sign of an original nRF24L01 or a clone
+    UnsupportedDataRate,
+    /// Leaving RX mode was refused because it is pinned with `pin_rx()`
+    RxPinned,
+    /// There was no packet to read: the RX FIFO was empty, or held a corrupt packet which
+    /// was flushed
+    NoPacket,
+    /// The TX FIFO was full, so the packet was not queued
+    TxFifoFull,
+    /// A payload of this many bytes exceeds [`MAX_PAYLOAD_BYTES`](crate::MAX_PAYLOAD_BYTES)
+    PayloadTooLong(usize),
+    /// `multicast()` was given this many addresses, more than the 16 it can report on
+    TooManyAddresses(usize),
+    /// The feature is not available on the chip variant selected by the `nrf24l01-plus`
+    /// feature
+    UnsupportedOnVariant,
+    /// The configuration is inconsistent, nothing was applied
+    InvalidConfig(ConfigError),
 }
 
 impl<SPIE: Debug> From<SPIE> for Error<SPIE> {
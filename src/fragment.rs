@@ -0,0 +1,163 @@
+//! Splitting messages larger than one payload into fragments
+//!
+//! Each fragment carries a 2-byte header followed by up to
+//! [`FRAGMENT_DATA_BYTES`] of data:
+//!
+//! * byte 0: sequence number of the fragment within the message
+//! * byte 1: flags, see [`MORE_FRAGMENTS`]
+//!
+//! Send the payloads yielded by a [`Fragmenter`] one by one with
+//! `send()`, and feed every received payload into a [`Reassembler`].
+//! Fragments may arrive out of order or more than once.
+
+use heapless::Vec;
+
+use crate::{Payload, MAX_PAYLOAD_BYTES};
+
+/// Header length of every fragment
+pub const HEADER_BYTES: usize = 2;
+/// Data bytes carried by a single fragment
+pub const FRAGMENT_DATA_BYTES: usize = MAX_PAYLOAD_BYTES - HEADER_BYTES;
+/// Maximum number of fragments per message
+pub const MAX_FRAGMENTS: usize = 16;
+/// Maximum length of a message that can be fragmented
+pub const MAX_MESSAGE_BYTES: usize = MAX_FRAGMENTS * FRAGMENT_DATA_BYTES;
+/// Flag bit set on every fragment but the last one of a message
+pub const MORE_FRAGMENTS: u8 = 1;
+
+/// Fragmentation errors
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FragmentError {
+    /// Message exceeds `MAX_MESSAGE_BYTES`
+    MessageTooLong,
+    /// Fragment has an invalid header or length, or contradicts earlier fragments
+    Malformed,
+}
+
+/// Splits a message into payloads
+///
+/// Iterate to obtain the payloads to send.
+pub struct Fragmenter<'a> {
+    message: &'a [u8],
+    seq: u8,
+    done: bool,
+}
+
+impl<'a> Fragmenter<'a> {
+    /// Prepare fragmenting `message`
+    pub fn new(message: &'a [u8]) -> Result<Self, FragmentError> {
+        if message.len() > MAX_MESSAGE_BYTES {
+            return Err(FragmentError::MessageTooLong);
+        }
+        Ok(Fragmenter {
+            message,
+            seq: 0,
+            done: false,
+        })
+    }
+}
+
+impl<'a> Iterator for Fragmenter<'a> {
+    type Item = Payload;
+
+    fn next(&mut self) -> Option<Payload> {
+        if self.done {
+            return None;
+        }
+        let len = self.message.len().min(FRAGMENT_DATA_BYTES);
+        let (data, rest) = self.message.split_at(len);
+        // An empty message still produces one (empty) fragment
+        self.done = rest.is_empty();
+
+        let mut buf = [0; MAX_PAYLOAD_BYTES];
+        buf[0] = self.seq;
+        buf[1] = if self.done { 0 } else { MORE_FRAGMENTS };
+        buf[HEADER_BYTES..HEADER_BYTES + len].copy_from_slice(data);
+
+        self.message = rest;
+        self.seq += 1;
+        Some(Payload::new(&buf[..HEADER_BYTES + len]))
+    }
+}
+
+/// Collects fragments until a message is complete
+pub struct Reassembler {
+    buf: [u8; MAX_MESSAGE_BYTES],
+    /// Bit `n` is set once fragment `n` was received
+    received: u16,
+    /// Fragment count and message length, known once the last fragment was received
+    last: Option<(usize, usize)>,
+}
+
+impl Default for Reassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reassembler {
+    /// Start without any fragments
+    pub fn new() -> Self {
+        Reassembler {
+            buf: [0; MAX_MESSAGE_BYTES],
+            received: 0,
+            last: None,
+        }
+    }
+
+    /// Discard a partially received message
+    pub fn reset(&mut self) {
+        self.received = 0;
+        self.last = None;
+    }
+
+    /// Add a received fragment
+    ///
+    /// Returns the full message once all of its fragments have been
+    /// received. Duplicates of already received fragments are ignored.
+    /// A malformed fragment is rejected without affecting the fragments
+    /// collected so far.
+    pub fn push(&mut self, fragment: &[u8]) -> Result<Option<Vec<u8, MAX_MESSAGE_BYTES>>, FragmentError> {
+        if fragment.len() < HEADER_BYTES {
+            return Err(FragmentError::Malformed);
+        }
+        let seq = usize::from(fragment[0]);
+        let more = fragment[1] & MORE_FRAGMENTS != 0;
+        let data = &fragment[HEADER_BYTES..];
+        if seq >= MAX_FRAGMENTS || (more && data.len() != FRAGMENT_DATA_BYTES) {
+            return Err(FragmentError::Malformed);
+        }
+
+        let offset = seq * FRAGMENT_DATA_BYTES;
+        let end = offset + data.len();
+        let consistent = match self.last {
+            Some((count, len)) => seq < count && more == (seq + 1 < count) && (more || end == len),
+            // The last fragment must come after everything seen so far
+            None => more || self.received >> seq <= 1,
+        };
+        if !consistent {
+            return Err(FragmentError::Malformed);
+        }
+
+        let bit = 1 << seq;
+        if self.received & bit != 0 {
+            return Ok(None);
+        }
+        self.buf[offset..end].copy_from_slice(data);
+        self.received |= bit;
+        if !more {
+            self.last = Some((seq + 1, end));
+        }
+
+        match self.last {
+            Some((count, len)) if self.received.count_ones() as usize == count => {
+                let mut message = Vec::new();
+                // Cannot fail, len <= MAX_MESSAGE_BYTES
+                let _ = message.extend_from_slice(&self.buf[..len]);
+                self.reset();
+                Ok(Some(message))
+            }
+            _ => Ok(None),
+        }
+    }
+}
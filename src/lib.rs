@@ -12,42 +12,66 @@
 #![no_std]
 #[macro_use]
 extern crate bitfield;
+#[cfg(feature = "mock")]
+extern crate std;
 
 use core::fmt;
 use core::fmt::Debug;
 
+use embedded_hal::blocking::delay::DelayUs;
 use embedded_hal::blocking::spi::Transfer as SpiTransfer;
 use embedded_hal::digital::v2::OutputPin;
 
 pub mod config;
-pub use crate::config::{CrcMode, DataRate, NRF24L01Config, NRF24L01Configuration, PALevel, RetransmitConfig};
+pub use crate::config::{decode_status, CrcMode, DataRate, InterruptStatus, NRF24L01Config, NRF24L01ConfigOwned, NRF24L01Configuration, PALevel, Pipe, PipeProfile, RetransmitConfig};
+use crate::config::{invalid_payload_length, ConfigError};
 pub mod setup;
+pub mod timing;
+#[cfg(feature = "mock")]
+pub mod mock;
+#[cfg(feature = "heapless")]
+pub mod fragment;
 
 mod registers;
-use crate::registers::{Config, Register, SetupAw, Status, FifoStatus, CD, RfCh};
+pub use crate::registers::{RegisterAddress, Status};
+use crate::registers::{Config, Register, SetupAw, FifoStatus, CD, RfCh};
 use crate::registers::{RfSetup, EnRxaddr, TxAddr, SetupRetr, EnAa, Dynpd, Feature};
 mod command;
-use crate::command::{Command, ReadRegister, WriteRegister, ReadRxPayloadWidth, ReadRxPayload, WriteTxPayload, FlushTx, FlushRx};
+use crate::command::{Command, ReadRegister, WriteRegister, ReadRxPayloadWidth, ReadRxPayload, WriteTxPayload, WriteAckPayload, FlushTx, FlushRx, Nop, register_command, R_REGISTER};
 mod payload;
 pub use crate::payload::Payload;
+mod address;
+pub use crate::address::Address;
 mod error;
 pub use crate::error::Error;
+mod monotonic;
+pub use crate::monotonic::Monotonic;
+mod tracer;
+pub use crate::tracer::Tracer;
 
 mod device;
 pub use crate::device::Device;
 mod rx;
-pub use crate::rx::Rx;
+pub use crate::rx::{LinkQuality, ReceivedPacket, Rx};
 mod tx;
-pub use crate::tx::Tx;
+pub use crate::tx::{Completion, LinkMargin, SendToken, ThroughputResult, Tx};
 mod mode;
-pub use crate::mode::{Mode, ChangeModes};
+pub use crate::mode::{Mode, ChangeModes, PowerState};
+mod dual;
+pub use crate::dual::DualRadio;
 
 /// Number of RX pipes with configurable addresses
 pub const PIPES_COUNT: usize = 6;
 /// Minimum address length
 pub const MIN_ADDR_BYTES: usize = 2;
+/// Minimum address width the chip can be set to
+pub const MIN_ADDR_WIDTH: usize = 3;
 /// Maximum address length
 pub const MAX_ADDR_BYTES: usize = 5;
+/// Maximum SPI clock frequency supported by the chip
+pub const MAX_SPI_HZ: u32 = 10_000_000;
+/// Maximum payload length
+pub const MAX_PAYLOAD_BYTES: usize = 32;
 
 /// Driver for the nRF24L01+
 ///
@@ -60,18 +84,44 @@ pub const MAX_ADDR_BYTES: usize = 5;
 /// where `D: `[`Device`](trait.Device.html)
 pub struct NRF24L01<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: SpiTransfer<u8>> {
     ce: CE,
+    ce_high: bool,
     csn: CSN,
     spi: SPI,
     config: Config,
     mode: Mode,
     nrf_config: NRF24L01Config<'a>,
+    tx_next_token: u8,
+    tx_in_flight: u8,
+    tx_flushed: u8,
+    spi_retries: u8,
+    broadcast_saved: Option<(RetransmitConfig, [bool; PIPES_COUNT])>,
+    pipe0_saved: Option<&'a [u8]>,
+    /// The LSBs of pipes 2-5, owned so `set_rx_addr_lsb()` can take one by value. These
+    /// take precedence over the addresses of those pipes in `nrf_config`.
+    rx_addr_lsbs: [u8; PIPES_COUNT],
+    rx_overflow_count: u32,
+    rx_was_full: bool,
+    last_send_ok: Option<bool>,
+    settle_us: u32,
+    last_rx_pipe: Option<u8>,
+    rx_pinned: bool,
+    tracer: Option<&'a mut dyn Tracer>,
 }
 
 impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: SpiTransfer<u8, Error = SPIE>, SPIE: Debug> fmt::Debug
     for NRF24L01<'a, E, CE, CSN, SPI>
 {
+    /// Shows the mode and the cached configuration, without touching the bus
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "NRF24L01")
+        f.debug_struct("NRF24L01")
+            .field("mode", &self.mode)
+            .field("data_rate", &self.nrf_config.data_rate)
+            .field("rf_channel", &self.nrf_config.rf_channel)
+            .field("pa_level", &self.nrf_config.pa_level)
+            .field("crc_mode", &self.nrf_config.crc_mode)
+            .field("address_width", &self.nrf_config.address_width)
+            .field("read_enabled_pipes", &self.nrf_config.read_enabled_pipes)
+            .finish()
     }
 }
 
@@ -79,7 +129,28 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
     NRF24L01<'a, E, CE, CSN, SPI>
 {
     /// Construct a new driver instance with specified configuration.
-    pub fn new_with_config(mut ce: CE, mut csn: CSN, spi: SPI, nrf_config: NRF24L01Config<'a>) -> Result<Self, Error<SPIE>> {
+    ///
+    /// The SPI bus must be clocked at no more than [`MAX_SPI_HZ`]. Faster clocks cause
+    /// intermittent data corruption that is easily mistaken for radio trouble.
+    pub fn new_with_config(ce: CE, csn: CSN, spi: SPI, nrf_config: NRF24L01Config<'a>) -> Result<Self, Error<SPIE>> {
+        let mut device = NRF24L01::connect(ce, csn, spi, nrf_config)?;
+
+        // TODO: activate features?
+
+        // Only differences to the cache are written below, but SETUP_AW may still hold the
+        // width of an earlier run when the radio kept its power
+        device.set_address_width(nrf_config.address_width)?;
+        device.set_nrf_configuration(nrf_config)?;
+
+        match device.update_config(|config| config.set_pwr_up(true)) {
+            Ok(_) => Ok(device),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Takes over the pins and the bus and checks that the chip responds, without
+    /// configuring it
+    fn connect(mut ce: CE, mut csn: CSN, spi: SPI, nrf_config: NRF24L01Config<'a>) -> Result<Self, Error<SPIE>> {
         ce.set_low().unwrap();
         csn.set_high().unwrap();
 
@@ -90,40 +161,417 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
         config.set_mask_max_rt(false);
         let mut device = NRF24L01 {
             ce,
+            ce_high: false,
             csn,
             spi,
             config,
             mode: Mode::Standby,
             nrf_config,
+            tx_next_token: 0,
+            tx_in_flight: 0,
+            tx_flushed: 0,
+            spi_retries: 0,
+            broadcast_saved: None,
+            pipe0_saved: None,
+            rx_addr_lsbs: [0; PIPES_COUNT],
+            rx_overflow_count: 0,
+            rx_was_full: false,
+            last_send_ok: None,
+            settle_us: 0,
+            last_rx_pipe: None,
+            rx_pinned: false,
+            tracer: None,
         };
+        for (lsb, addr) in device.rx_addr_lsbs.iter_mut().zip(nrf_config.rx_addrs.iter()).skip(2) {
+            *lsb = addr.first().copied().unwrap_or(0);
+        }
 
         match device.is_connected() {
-            Err(e) => return Err(e),
-            Ok(false) => return Err(Error::NotConnected),
-            _ => {}
+            Err(e) => Err(e),
+            Ok(false) => Err(Error::NotConnected),
+            Ok(true) => Ok(device),
         }
+    }
 
-        // TODO: activate features?
-
-        device.set_nrf_configuration(nrf_config)?;
-
-        match device.update_config(|config| config.set_pwr_up(true)) {
-            Ok(_) => Ok(device),
-            Err(err) => Err(err),
-        }
+    /// Constructs a new driver instance and writes every setting of `cfg` to the chip
+    ///
+    /// Meant for a configuration defined once as a `static`, see
+    /// [`configure()`](trait.NRF24L01Configuration.html#tymethod.configure), which is
+    /// applied once and leaves the device in the `mode` of the configuration.
+    pub fn with_config(ce: CE, csn: CSN, spi: SPI, cfg: &'a NRF24L01ConfigOwned) -> Result<Self, Error<SPIE>> {
+        let mut device = NRF24L01::connect(ce, csn, spi, cfg.as_config())?;
+        device.update_config(|config| config.set_pwr_up(true))?;
+        device.configure(cfg)?;
+        Ok(device)
     }
 
     /// Constructs a new driver instance with default configuration
+    ///
+    /// The SPI bus must be clocked at no more than [`MAX_SPI_HZ`].
     pub fn new(ce: CE, csn: CSN, spi: SPI) -> Result<Self, Error<SPIE>> {
         NRF24L01::new_with_config(ce, csn, spi, NRF24L01Config::default())
     }
 
+    /// Retry failed SPI transfers up to `retries` times before giving up (defaults to 0).
+    ///
+    /// CSN is released and re-asserted for every attempt. This only helps with transient bus
+    /// errors, e.g. on long or noisy traces, not with logical failures of the radio.
+    pub fn with_spi_retries(mut self, retries: u8) -> Self {
+        self.spi_retries = retries;
+        self
+    }
+
+    /// Hand every SPI transaction to `tracer`, for debugging
+    ///
+    /// Without a tracer the only overhead is a check for `None`.
+    pub fn with_tracer(mut self, tracer: &'a mut dyn Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Gives back the CE pin, the CSN pin and the SPI bus
+    ///
+    /// CE is pulled low, but the radio is otherwise left as it is, use
+    /// [`shutdown_and_release()`](#method.shutdown_and_release) to power it down as well.
+    pub fn release(mut self) -> (CE, CSN, SPI) {
+        self.ce_disable();
+        (self.ce, self.csn, self.spi)
+    }
+
+    /// Powers the radio down, then gives back the CE pin, the CSN pin and the SPI bus
+    pub fn shutdown_and_release(mut self) -> Result<(CE, CSN, SPI), Error<SPIE>> {
+        self.unpin_rx();
+        self.to_power_down()?;
+        Ok(self.release())
+    }
+
+    /// Run `f` with the device in Standby, restoring the previous mode afterwards.
+    ///
+    /// Per the datasheet, registers such as `RF_SETUP`, `RF_CH` and the addresses should
+    /// only be changed in Standby or Power Down. The configuration setters use this to
+    /// bracket their writes, so changing e.g. the data rate while in RX is safe.
+    pub fn configure_in_standby<F, R>(&mut self, f: F) -> Result<R, Error<SPIE>>
+    where
+        F: FnOnce(&mut Self) -> Result<R, Error<SPIE>>,
+    {
+        let was_rx = self.mode == Mode::Rx;
+        let was_tx = self.mode == Mode::Tx;
+        if was_rx || was_tx {
+            self.to_standby()?;
+        }
+
+        let result = f(self);
+
+        // Restore the mode even if `f` failed, but report the error of `f` first
+        let restored = if was_rx {
+            self.to_rx()
+        } else if was_tx {
+            self.to_tx()
+        } else {
+            Ok(())
+        };
+        result.and_then(|value| restored.map(|()| value))
+    }
+
+    /// Mode the driver believes the chip to be in
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    fn power_state(&self) -> PowerState {
+        match self.mode {
+            Mode::Standby => PowerState::Standby,
+            Mode::PowerDown => PowerState::PowerDown,
+            Mode::Rx => PowerState::Rx,
+            Mode::Tx => PowerState::Tx,
+        }
+    }
+
+    fn write_rx_addr_lsb(&mut self, pipe_no: usize, lsb: u8) -> Result<Status, Error<SPIE>> {
+        macro_rules! w {
+            ( $($no: expr, $name: ident);+ ) => (
+                match pipe_no {
+                    $(
+                        $no => {
+                            use crate::registers::$name;
+                            self.write_register($name(lsb))
+                        }
+                    )+
+                        _ => panic!("No such pipe {}", pipe_no)
+                }
+            )
+        }
+        w!(2, RxAddrP2;
+           3, RxAddrP3;
+           4, RxAddrP4;
+           5, RxAddrP5)
+    }
+
+    fn read_rx_pw(&mut self, pipe_no: usize) -> Result<u8, Error<SPIE>> {
+        macro_rules! r {
+            ( $($no: expr, $name: ident);+ ) => (
+                match pipe_no {
+                    $(
+                        $no => {
+                            use crate::registers::$name;
+                            self.read_register::<$name>()?.1.get()
+                        }
+                    )+
+                        _ => panic!("No such pipe {}", pipe_no)
+                }
+            )
+        }
+        Ok(r!(0, RxPwP0;
+              1, RxPwP1;
+              2, RxPwP2;
+              3, RxPwP3;
+              4, RxPwP4;
+              5, RxPwP5))
+    }
+
+    fn write_rx_pw(&mut self, pipe_no: usize, len: u8) -> Result<Status, Error<SPIE>> {
+        macro_rules! w {
+            ( $($no: expr, $name: ident);+ ) => (
+                match pipe_no {
+                    $(
+                        $no => {
+                            use crate::registers::$name;
+                            let mut register = $name(0);
+                            register.set(len);
+                            self.write_register(register)
+                        }
+                    )+
+                        _ => panic!("No such pipe {}", pipe_no)
+                }
+            )
+        }
+        w!(0, RxPwP0;
+           1, RxPwP1;
+           2, RxPwP2;
+           3, RxPwP3;
+           4, RxPwP4;
+           5, RxPwP5)
+    }
+
+    fn write_payload_lengths(&mut self, lengths: [Option<u8>; PIPES_COUNT]) -> Result<(), Error<SPIE>> {
+        let mut bools = [true; PIPES_COUNT];
+        for (i, len) in lengths.iter().enumerate() {
+            bools[i] = len.is_none();
+        }
+        let dynpd = Dynpd::from_bools(&bools);
+        if dynpd.0 != 0 {
+            self.update_register::<Feature, _, _>(|feature| {
+                feature.set_en_dpl(true);
+            })?;
+        }
+        self.write_register(dynpd)?;
+
+        // Set static payload lengths
+        for (pipe, len) in lengths.iter().enumerate() {
+            self.write_rx_pw(pipe, len.unwrap_or(0))?;
+        }
+
+        self.nrf_config.pipe_payload_lengths = lengths;
+
+        Ok(())
+    }
+
+    /// Runs a single SPI transaction on `buf`, retrying as configured with `with_spi_retries()`
+    ///
+    /// `encode` fills the zeroed buffer with the command before every attempt, as a failed
+    /// transfer may have clobbered it. On success `buf` holds the response.
+    fn transfer_command(&mut self, buf: &mut [u8], encode: impl Fn(&mut [u8])) -> Result<(), Error<SPIE>> {
+        let len = buf.len();
+        let mut attempts_left = self.spi_retries;
+        loop {
+            // Without leftovers of an earlier attempt that could pass for response data
+            buf.fill(0);
+            encode(buf);
+            let opcode = buf[0];
+
+            // SPI transaction
+            self.csn.set_low().unwrap();
+            let transfer_result = self.spi.transfer(buf).map(|response| response.len());
+            self.csn.set_high().unwrap();
+            // Propagate Err only after csn.set_high():
+            match transfer_result {
+                Ok(got) if got == len => {
+                    if let Some(tracer) = self.tracer.as_mut() {
+                        tracer.on_command(opcode, buf);
+                    }
+                    return Ok(());
+                }
+                Ok(_) | Err(_) if attempts_left > 0 => attempts_left -= 1,
+                Ok(got) => return Err(Error::ShortTransfer { expected: len, got }),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Reads the first byte of every register in `addrs` into `out`
+    ///
+    /// The chip has no auto-increment, so this is still one `R_REGISTER` transaction per
+    /// address, but they share a single scratch buffer without any decoding in between. Meant
+    /// for tools polling the whole register map. Panics if `addrs` and `out` differ in
+    /// length.
+    pub fn read_registers(&mut self, addrs: &[u8], out: &mut [u8]) -> Result<(), Error<SPIE>> {
+        assert_eq!(addrs.len(), out.len());
+
+        let mut buf = [0; 2];
+        for (addr, value) in addrs.iter().zip(out.iter_mut()) {
+            self.transfer_command(&mut buf, |buf| buf[0] = register_command(R_REGISTER, *addr))?;
+            *value = buf[1];
+        }
+        Ok(())
+    }
+
+    /// Writes every part of a pipe profile, with reception disabled in between
+    fn write_pipe_profile(&mut self, profile: &PipeProfile<'a>) -> Result<(), Error<SPIE>> {
+        self.configure_in_standby(|device| {
+            device.set_read_enabled_pipes(&[false; PIPES_COUNT])?;
+            for (pipe, addr) in profile.rx_addrs.iter().enumerate() {
+                device.set_rx_addrs(pipe, addr)?;
+            }
+            device.write_payload_lengths(profile.pipe_payload_lengths)?;
+            device.set_auto_ack(profile.auto_ack_pipes)?;
+            device.set_read_enabled_pipes(&profile.read_enabled_pipes)
+        })
+    }
+
+    /// 250 Kbps is only available on the nRF24L01+
+    fn check_data_rate(rate: DataRate) -> Result<(), Error<SPIE>> {
+        if cfg!(not(feature = "nrf24l01-plus")) && rate == DataRate::R250Kbps {
+            return Err(Error::UnsupportedOnVariant);
+        }
+        Ok(())
+    }
+
+    /// Points `TX_ADDR` and `RX_ADDR_P0` elsewhere without touching the cache
+    fn write_tx_addrs(&mut self, tx_addr: &[u8], rx_addr_p0: &[u8]) -> Result<(), Error<SPIE>> {
+        use crate::registers::RxAddrP0;
+
+        self.configure_in_standby(|device| {
+            device.write_register(TxAddr::new(tx_addr))?;
+            device.write_register(RxAddrP0::new(rx_addr_p0))?;
+            Ok(())
+        })
+    }
+
     /// Reads and validates content of the `SETUP_AW` register.
+    ///
+    /// Fails with [`Error::BusStuckLow`] or [`Error::BusStuckHigh`] when STATUS and
+    /// `SETUP_AW` both read back as all zeroes or all ones. STATUS always has its reserved
+    /// bit 7 clear and `SETUP_AW` is never 0, so this points at the wiring rather than the
+    /// radio.
     pub fn is_connected(&mut self) -> Result<bool, Error<SPIE>> {
-        let (_, setup_aw) = self.read_register::<SetupAw>()?;
+        let (status, setup_aw) = self.read_register::<SetupAw>()?;
+        match u8::from(status) {
+            0x00 if setup_aw.aw() == 0 => return Err(Error::BusStuckLow),
+            0xFF if setup_aw.aw() == 0b11 => return Err(Error::BusStuckHigh),
+            _ => {}
+        }
         let valid = setup_aw.aw() <= 3;
         Ok(valid)
     }
+
+    /// Drop the outcomes of tracked packets, once another path emptied the TX FIFO
+    fn forget_tracked(&mut self) {
+        self.tx_in_flight = 0;
+        self.tx_flushed = 0;
+    }
+
+    /// Count every time the RX FIFO is newly observed to be full
+    fn track_rx_overflow(&mut self, fifo_status: &FifoStatus) {
+        if fifo_status.rx_full() && !self.rx_was_full {
+            self.rx_overflow_count = self.rx_overflow_count.saturating_add(1);
+        }
+        self.rx_was_full = fifo_status.rx_full();
+    }
+
+    /// Reads the packet at the head of the RX FIFO, along with the pipe it arrived on
+    ///
+    /// `None` if the RX FIFO is empty, as `RX_P_NO` reads `0b111` then. A width beyond
+    /// `MAX_PAYLOAD_BYTES` means the packet is corrupt; the datasheet says to flush the RX
+    /// FIFO, after which `None` is returned as well. The pipe is taken from the `STATUS`
+    /// clocked out while the payload itself is read, so it always matches the packet.
+    fn read_rx_fifo(&mut self) -> Result<Option<(u8, Payload)>, Error<SPIE>> {
+        let (status, payload_width) = self.send_command(&ReadRxPayloadWidth)?;
+        if status.rx_p_no() == 0b111 {
+            return Ok(None);
+        }
+        if usize::from(payload_width) > MAX_PAYLOAD_BYTES {
+            self.send_command(&FlushRx)?;
+            return Ok(None);
+        }
+
+        let (status, payload) = self.send_command(&ReadRxPayload::new(payload_width as usize))?;
+        Ok(Some((status.rx_p_no(), payload)))
+    }
+
+    /// Flushes both FIFOs and clears all interrupt flags
+    ///
+    /// Registers are left alone. Returns STATUS as it is afterwards.
+    pub fn clear_all(&mut self) -> Result<Status, Error<SPIE>> {
+        self.send_command(&FlushTx)?;
+        self.send_command(&FlushRx)?;
+        self.clear_interrupts()?;
+        let (status, ()) = self.send_command(&Nop)?;
+        Ok(status)
+    }
+
+    /// Reads the state of both FIFOs with a single SPI transaction
+    ///
+    /// Unlike `tx_empty()`, `rx_queue_empty()` and friends this doesn't change the mode.
+    pub fn fifo_status(&mut self) -> Result<config::FifoSummary, Error<SPIE>> {
+        let (_, fifo_status) = self.read_register::<FifoStatus>()?;
+        Ok(config::FifoSummary {
+            rx_empty: fifo_status.rx_empty(),
+            rx_full: fifo_status.rx_full(),
+            tx_empty: fifo_status.tx_empty(),
+            tx_full: fifo_status.tx_full(),
+            tx_reuse: fifo_status.tx_reuse(),
+        })
+    }
+
+    /// Reads and clears all interrupt flags in a single SPI transaction
+    ///
+    /// Meant for interrupt handlers: the returned flags are those set right before clearing.
+    pub fn take_interrupts(&mut self) -> Result<config::InterruptStatus, Error<SPIE>> {
+        // The STATUS returned by the write is the one from before the flags were cleared
+        let status = self.clear_interrupts()?;
+        Ok(config::InterruptStatus::from(status))
+    }
+
+    fn clear_interrupts(&mut self) -> Result<Status, Error<SPIE>> {
+        self.clear_flags(|flags| {
+            flags.set_rx_dr(true);
+            flags.set_tx_ds(true);
+            flags.set_max_rt(true);
+        })
+    }
+
+    /// Clears the interrupt flags set by `flags` in a single write
+    ///
+    /// The flags of `STATUS` are cleared by writing 1 to them, the others are left alone.
+    /// Returns `STATUS` from before the write.
+    fn clear_flags(&mut self, flags: impl FnOnce(&mut Status)) -> Result<Status, Error<SPIE>> {
+        let mut clear = Status(0);
+        flags(&mut clear);
+        self.write_register(clear)
+    }
+
+    /// Flushes the TX FIFO and clears `TX_DS` and `MAX_RT`
+    ///
+    /// This is also the way out of `MAX_RT`: the failed packet isn't removed from the TX FIFO
+    /// and TX won't continue while the flag is set, so without it the FIFO never drains.
+    fn reset_tx(&mut self) -> Result<(), Error<SPIE>> {
+        self.send_command(&FlushTx)?;
+        self.clear_flags(|flags| {
+            flags.set_tx_ds(true);
+            flags.set_max_rt(true);
+        })?;
+        Ok(())
+    }
 }
 
 impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: SpiTransfer<u8, Error = SPIE>, SPIE: Debug> Device
@@ -133,10 +581,12 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
 
     fn ce_enable(&mut self) {
         self.ce.set_high().unwrap();
+        self.ce_high = true;
     }
 
     fn ce_disable(&mut self) {
         self.ce.set_low().unwrap();
+        self.ce_high = false;
     }
 
     fn send_command<C: Command>(
@@ -144,18 +594,10 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
         command: &C,
     ) -> Result<(Status, C::Response), Self::Error> {
         // Allocate storage
-        let mut buf_storage = [0; 33];
+        let mut buf_storage = [0; 1 + MAX_PAYLOAD_BYTES];
         let len = command.len();
         let buf = &mut buf_storage[0..len];
-        // Serialize the command
-        command.encode(buf);
-
-        // SPI transaction
-        self.csn.set_low().unwrap();
-        let transfer_result = self.spi.transfer(buf).map(|_| {});
-        self.csn.set_high().unwrap();
-        // Propagate Err only after csn.set_high():
-        transfer_result?;
+        self.transfer_command(buf, |buf| command.encode(buf))?;
 
         // Parse response
         let status = Status(buf[0]);
@@ -200,18 +642,33 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
             Mode::PowerDown => match self.update_config(|config| config.set_pwr_up(true)) {
                 Ok(()) => {
                     self.mode = Mode::Standby;
+                    // Starts a new chain of transitions, nothing else is pending after power down
+                    self.settle_us = timing::POWER_DOWN_TO_STANDBY_US;
                     Ok(())
                 },
                 Err(err) => Err(err),
             },
+            Mode::Rx if self.rx_pinned => Err(Error::RxPinned),
             Mode::Rx | Mode::Tx => {
                 self.ce_disable();
                 self.mode = Mode::Standby;
+                // Standby needs no settling, only the next switch to an active mode does
+                self.settle_us = 0;
                 Ok(())
             },
         }
     }
 
+    fn pin_rx(&mut self) -> Result<(), Self::Error> {
+        self.to_rx()?;
+        self.rx_pinned = true;
+        Ok(())
+    }
+
+    fn unpin_rx(&mut self) {
+        self.rx_pinned = false;
+    }
+
     fn to_power_down(&mut self) -> Result<(), Self::Error> {
         match self.mode {
             Mode::Standby => match self.update_config(|config| config.set_pwr_up(false)) {
@@ -237,6 +694,8 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
                 match self.update_config(|config| config.set_prim_rx(true)) {
                     Ok(_) => {
                         self.ce_enable();
+                        self.mode = Mode::Rx;
+                        self.settle_us = self.settle_us.saturating_add(timing::STANDBY_TO_ACTIVE_US);
                         Ok(())
                     },
                     Err(err) => Err(err),
@@ -254,7 +713,10 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
         match self.mode {
             Mode::Standby => {
                 match self.update_config(|config| config.set_prim_rx(false)) {
-                    Ok(_) => Ok(()),
+                    Ok(_) => {
+                        self.mode = Mode::Tx;
+                        Ok(())
+                    },
                     Err(err) => Err(err),
                 }
             },
@@ -265,6 +727,67 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
             Mode::Tx => Ok(()),
         }
     }
+
+    fn is_powered_up(&mut self) -> Result<bool, Self::Error> {
+        let (_, config) = self.read_register::<Config>()?;
+        Ok(config.pwr_up())
+    }
+
+    fn deep_sleep(&mut self) -> Result<(), Self::Error> {
+        self.to_power_down()
+    }
+
+    fn wake(&mut self, delay: &mut impl DelayUs<u32>) -> Result<(), Self::Error> {
+        self.to_standby()?;
+        self.wait_ready(delay)
+    }
+
+    fn wait_ready(&mut self, delay: &mut impl DelayUs<u32>) -> Result<(), Self::Error> {
+        if self.settle_us > 0 {
+            delay.delay_us(self.settle_us);
+            self.settle_us = 0;
+        }
+        Ok(())
+    }
+
+    fn read_power_state(&mut self) -> Result<(bool, bool), Self::Error> {
+        let (_, config) = self.read_register::<Config>()?;
+        Ok((config.pwr_up(), config.prim_rx()))
+    }
+
+    fn resync_mode(&mut self) -> Result<(), Self::Error> {
+        let (_, config) = self.read_register::<Config>()?;
+        self.mode = if !config.pwr_up() {
+            Mode::PowerDown
+        } else if config.prim_rx() {
+            if self.ce_high {
+                Mode::Rx
+            } else {
+                Mode::Standby
+            }
+        } else if self.ce_high || self.mode == Mode::Tx {
+            // TX mode with CE low is Standby-II waiting for a packet
+            Mode::Tx
+        } else {
+            Mode::Standby
+        };
+        self.config = config;
+        Ok(())
+    }
+
+    fn to_rx_clean(&mut self) -> Result<(), Self::Error> {
+        self.to_standby()?;
+        self.send_command(&FlushTx)?;
+        self.clear_interrupts()?;
+        self.to_rx()
+    }
+
+    fn to_tx_clean(&mut self) -> Result<(), Self::Error> {
+        self.to_standby()?;
+        self.send_command(&FlushRx)?;
+        self.clear_interrupts()?;
+        self.to_tx()
+    }
 }
 
 impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: SpiTransfer<u8, Error = SPIE>, SPIE: Debug> Rx
@@ -282,20 +805,25 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
             self.to_rx()?;
         }
 
-        let mut clear = Status(0);
-        clear.set_rx_dr(true);
-        clear.set_tx_ds(true);
-        clear.set_max_rt(true);
-        self.write_register(clear)?;
+        self.clear_interrupts()?;
 
-        self.read_register::<FifoStatus>()
-            .map(|(status, fifo_status)| {
-                if !fifo_status.rx_empty() {
-                    Some(status.rx_p_no())
-                } else {
-                    None
-                }
-            })
+        let (status, fifo_status) = self.read_register::<FifoStatus>()?;
+        self.track_rx_overflow(&fifo_status);
+        if !fifo_status.rx_empty() {
+            Ok(Some(status.rx_p_no()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn has_data(&mut self) -> Result<Option<u8>, Self::Error> {
+        // STATUS is clocked out along with the read of FIFO_STATUS
+        let (status, fifo_status) = self.read_register::<FifoStatus>()?;
+        if !fifo_status.rx_empty() {
+            Ok(Some(status.rx_p_no()))
+        } else {
+            Ok(None)
+        }
     }
 
     /// Is an in-band RF signal detected?
@@ -313,6 +841,79 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
             .map(|(_, cd)| cd.0 & 1 == 1)
     }
 
+    fn has_carrier_settled(&mut self, delay: &mut impl DelayUs<u32>) -> Result<bool, Self::Error> {
+        if self.mode != Mode::Rx {
+            self.to_rx()?;
+        }
+        self.wait_ready(delay)?;
+        delay.delay_us(timing::CARRIER_DETECT_US);
+
+        self.read_register::<CD>()
+            .map(|(_, cd)| cd.0 & 1 == 1)
+    }
+
+    /// Sample the carrier detect over a window and return the duty cycle.
+    ///
+    /// Stays in RX mode on the current channel and returns the percentage (0-100) of the
+    /// samples where `RPD` was set.
+    fn measure_channel_occupancy(&mut self, samples: u16, delay: &mut impl DelayUs<u32>) -> Result<u8, Self::Error> {
+        if self.mode != Mode::Rx {
+            self.to_rx()?;
+        }
+        self.wait_ready(delay)?;
+
+        if samples == 0 {
+            return Ok(0);
+        }
+
+        let mut hits = 0u32;
+        for _ in 0..samples {
+            delay.delay_us(timing::CARRIER_DETECT_US);
+            let (_, cd) = self.read_register::<CD>()?;
+            if cd.0 & 1 == 1 {
+                hits += 1;
+            }
+        }
+
+        Ok((hits * 100 / u32::from(samples)) as u8)
+    }
+
+    fn link_quality(&mut self, samples: u16, delay: &mut impl DelayUs<u32>) -> Result<LinkQuality, Self::Error> {
+        let (_, observe_tx) = self.read_register::<registers::ObserveTx>()?;
+        let rpd_fraction = self.measure_channel_occupancy(samples, delay)?;
+        Ok(LinkQuality {
+            rpd_fraction,
+            last_arc: observe_tx.arc_cnt(),
+        })
+    }
+
+    fn spectrum_scan(&mut self, delay: &mut impl DelayUs<u32>, dwell_us: u32) -> Result<[u8; 126], Self::Error> {
+        let previous_state = self.power_state();
+        let previous_channel = self.nrf_config.rf_channel;
+        let samples = (dwell_us / timing::CARRIER_DETECT_US).max(1);
+
+        let mut energy = [0; 126];
+        for (channel, energy) in energy.iter_mut().enumerate() {
+            self.set_rf_channel(channel as u8)?;
+            self.to_rx()?;
+            self.wait_ready(delay)?;
+
+            let mut hits = 0;
+            for _ in 0..samples {
+                delay.delay_us(timing::CARRIER_DETECT_US);
+                let (_, cd) = self.read_register::<CD>()?;
+                if cd.0 & 1 == 1 {
+                    hits += 1;
+                }
+            }
+            *energy = (hits * 255 / samples) as u8;
+        }
+
+        self.set_rf_channel(previous_channel)?;
+        self.set_power_state(previous_state)?;
+        Ok(energy)
+    }
+
     /// Is the RX queue empty?
     fn rx_queue_empty(&mut self) -> Result<bool, Self::Error> {
         if self.mode != Mode::Rx {
@@ -335,14 +936,174 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
 
     /// Read the next received packet
     fn read(&mut self) -> Result<Payload, Self::Error> {
+        self.read_with_pipe().map(|(_, payload)| payload)
+    }
+
+    fn read_with_pipe(&mut self) -> Result<(u8, Payload), Self::Error> {
         if self.mode != Mode::Rx {
             self.to_rx()?;
         }
 
-        let (_, payload_width) = self.send_command(&ReadRxPayloadWidth)?;
-        let (_, payload) = self.send_command(&ReadRxPayload::new(payload_width as usize))?;
-        Ok(payload)
-    }
+        let (_, fifo_status) = self.read_register::<FifoStatus>()?;
+        self.track_rx_overflow(&fifo_status);
+
+        let (pipe, payload) = self.read_rx_fifo()?.ok_or(Error::NoPacket)?;
+        self.last_rx_pipe = Some(pipe);
+        Ok((pipe, payload))
+    }
+
+    fn last_rx_pipe(&self) -> Option<u8> {
+        self.last_rx_pipe
+    }
+
+    fn read_all(&mut self, out: &mut [Payload]) -> Result<usize, Self::Error> {
+        if self.mode != Mode::Rx {
+            self.to_rx()?;
+        }
+
+        let (_, fifo_status) = self.read_register::<FifoStatus>()?;
+        self.track_rx_overflow(&fifo_status);
+
+        let mut count = 0;
+        while count < out.len() {
+            match self.read_rx_fifo()? {
+                Some((_, payload)) => out[count] = payload,
+                None => break,
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn rx_overflow_count(&self) -> u32 {
+        self.rx_overflow_count
+    }
+
+    fn reset_rx_overflow_count(&mut self) {
+        self.rx_overflow_count = 0;
+    }
+
+    fn receive(&mut self) -> Result<Option<ReceivedPacket>, Self::Error> {
+        if self.mode != Mode::Rx {
+            self.to_rx()?;
+        }
+
+        let (pipe, payload) = match self.read_rx_fifo()? {
+            Some(packet) => packet,
+            None => return Ok(None),
+        };
+        let (_, fifo_status) = self.read_register::<FifoStatus>()?;
+        Ok(Some(ReceivedPacket {
+            pipe,
+            payload,
+            fifo_now_empty: fifo_status.rx_empty(),
+        }))
+    }
+
+    fn read_timeout(&mut self, delay: &mut impl DelayUs<u32>, timeout_us: u32) -> Result<Option<(u8, Payload)>, Self::Error> {
+        const POLL_INTERVAL_US: u32 = 10;
+
+        if self.mode != Mode::Rx {
+            self.to_rx()?;
+        }
+        self.wait_ready(delay)?;
+
+        let mut elapsed = 0;
+        loop {
+            if let Some(packet) = self.receive()? {
+                return Ok(Some((packet.pipe, packet.payload)));
+            }
+            if elapsed >= timeout_us {
+                return Ok(None);
+            }
+            delay.delay_us(POLL_INTERVAL_US);
+            elapsed = elapsed.saturating_add(POLL_INTERVAL_US);
+        }
+    }
+
+    fn poll_once(&mut self, delay: &mut impl DelayUs<u32>) -> Result<Option<(u8, Payload)>, Self::Error> {
+        if self.mode != Mode::Rx {
+            self.to_rx()?;
+        }
+        self.wait_ready(delay)?;
+
+        let dynamic_payload = self.nrf_config.pipe_payload_lengths.iter().any(Option::is_none);
+        delay.delay_us(timing::air_time_us(
+            MAX_PAYLOAD_BYTES as u8,
+            self.nrf_config.data_rate,
+            self.nrf_config.address_width,
+            self.nrf_config.crc_mode,
+            dynamic_payload,
+        ));
+
+        let packet = self.receive()?;
+        if !self.rx_pinned {
+            self.to_standby()?;
+        }
+        Ok(packet.map(|packet| (packet.pipe, packet.payload)))
+    }
+
+    fn set_ack_payloads(&mut self, payloads: &[(Pipe, &[u8])]) -> Result<(), Self::Error> {
+        if let Some((_, data)) = payloads.iter().find(|(_, data)| data.len() > MAX_PAYLOAD_BYTES) {
+            return Err(Error::PayloadTooLong(data.len()));
+        }
+        if payloads.len() > 3 {
+            return Err(Error::TxFifoFull);
+        }
+
+        for (pipe, data) in payloads {
+            // The STATUS returned is from before the write, if the FIFO was full it got dropped
+            let (status, ()) = self.send_command(&WriteAckPayload::new(pipe.index() as u8, data))?;
+            if status.tx_full() {
+                return Err(Error::TxFifoFull);
+            }
+        }
+        Ok(())
+    }
+
+    fn respond(&mut self, pipe: Pipe, ack_data: &[u8]) -> Result<(), Self::Error> {
+        let (_, feature) = self.read_register::<Feature>()?;
+        let (_, dynpd) = self.read_register::<Dynpd>()?;
+        if !feature.en_dpl() || !dynpd.dpl_p(pipe.index()) {
+            return Err(Error::DynamicPayloadRequired);
+        }
+        if !feature.en_ack_pay() {
+            return Err(Error::AckPayloadRequired);
+        }
+
+        self.set_ack_payloads(&[(pipe, ack_data)])
+    }
+
+    #[cfg(feature = "heapless")]
+    fn drain_by_pipe(&mut self) -> Result<heapless::Vec<(u8, Payload), 3>, Self::Error> {
+        let mut packets = heapless::Vec::new();
+        while !packets.is_full() {
+            match self.receive()? {
+                Some(packet) => {
+                    // Cannot fail, there is room
+                    let _ = packets.push((packet.pipe, packet.payload));
+                }
+                None => break,
+            }
+        }
+        Ok(packets)
+    }
+
+    fn rx_health_check(&mut self) -> Result<bool, Self::Error> {
+        let (_, fifo_status) = self.read_register::<FifoStatus>()?;
+        if fifo_status.rx_empty() {
+            return Ok(true);
+        }
+
+        let (status, payload_width) = self.send_command(&ReadRxPayloadWidth)?;
+        if usize::from(payload_width) <= MAX_PAYLOAD_BYTES && usize::from(status.rx_p_no()) < PIPES_COUNT {
+            return Ok(true);
+        }
+
+        self.send_command(&FlushRx)?;
+        self.clear_flags(|flags| flags.set_rx_dr(true))?;
+        Ok(false)
+    }
 }
 
 impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: SpiTransfer<u8, Error = SPIE>, SPIE: Debug> Tx
@@ -368,6 +1129,11 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
         Ok(fifo_status.tx_full())
     }
 
+    fn is_reusing_tx_payload(&mut self) -> Result<bool, Self::Error> {
+        let (_, fifo_status) = self.read_register::<FifoStatus>()?;
+        Ok(fifo_status.tx_reuse())
+    }
+
     fn can_send(&mut self) -> Result<bool, Self::Error> {
         if self.mode != Mode::Tx {
             self.to_tx()?;
@@ -378,15 +1144,281 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
     }
 
     fn send(&mut self, packet: &[u8]) -> Result<(), Self::Error> {
+        self.load_payload(packet)?;
+        self.trigger();
+        Ok(())
+    }
+
+    fn load_payload(&mut self, packet: &[u8]) -> Result<(), Self::Error> {
+        if packet.len() > MAX_PAYLOAD_BYTES {
+            return Err(Error::PayloadTooLong(packet.len()));
+        }
         if self.mode != Mode::Tx {
             self.to_tx()?;
         }
 
-        self.send_command(&WriteTxPayload::new(packet))?;
-        self.ce_enable();
+        // The STATUS returned is from before the write, if the FIFO was full it got dropped
+        let (status, ()) = self.send_command(&WriteTxPayload::new(packet))?;
+        if status.tx_full() {
+            return Err(Error::TxFifoFull);
+        }
+        self.last_send_ok = None;
         Ok(())
     }
 
+    fn send_blocking(&mut self, packet: &[u8]) -> Result<(), Self::Error> {
+        if packet.len() > MAX_PAYLOAD_BYTES {
+            return Err(Error::PayloadTooLong(packet.len()));
+        }
+        if self.mode != Mode::Tx {
+            self.to_tx()?;
+        }
+
+        loop {
+            let (status, fifo_status) = self.read_register::<FifoStatus>()?;
+            if !fifo_status.tx_full() {
+                break;
+            }
+            // The FIFO only drains while CE is high
+            self.ce_enable();
+
+            if status.max_rt() {
+                self.reset_tx()?;
+            }
+        }
+        self.send(packet)
+    }
+
+    fn send_if_clear(&mut self, packet: &[u8], delay: &mut impl DelayUs<u32>) -> nb::Result<(), Self::Error> {
+        if self.has_carrier_settled(delay)? {
+            return Err(nb::Error::WouldBlock);
+        }
+        self.send(packet)?;
+        Ok(())
+    }
+
+    fn trigger(&mut self) {
+        self.ce_enable();
+    }
+
+    fn send_to(&mut self, addr: &[u8], packet: &[u8]) -> Result<bool, Self::Error> {
+        self.write_tx_addrs(addr, addr)?;
+
+        let sent = self.send(packet).and_then(|()| nb::block!(self.poll_send()));
+
+        // Restore the addresses even if sending failed
+        let restored = self.write_tx_addrs(self.nrf_config.tx_addr, self.nrf_config.rx_addrs[0]);
+        sent.and_then(|success| restored.map(|()| success))
+    }
+
+    #[cfg(feature = "heapless")]
+    fn multicast(&mut self, addrs: &[&[u8]], packet: &[u8]) -> Result<heapless::Vec<bool, 16>, Self::Error> {
+        if addrs.len() > 16 {
+            return Err(Error::TooManyAddresses(addrs.len()));
+        }
+
+        let mut results = heapless::Vec::new();
+        let mut sent = Ok(());
+        for addr in addrs {
+            sent = self.write_tx_addrs(addr, addr).and_then(|()| {
+                self.send(packet)?;
+                let success = nb::block!(self.poll_send())?;
+                // Cannot fail, there are at most 16 addresses
+                let _ = results.push(success);
+                Ok(())
+            });
+            if sent.is_err() {
+                break;
+            }
+        }
+
+        // Restore the addresses even if sending failed
+        let restored = self.write_tx_addrs(self.nrf_config.tx_addr, self.nrf_config.rx_addrs[0]);
+        sent.and(restored).map(|()| results)
+    }
+
+    fn send_at_pa(&mut self, packet: &[u8], pa: PALevel) -> Result<bool, Self::Error> {
+        let original = self.nrf_config.pa_level;
+        if pa != original {
+            self.set_pa_level(pa)?;
+        }
+
+        let sent = self
+            .send(packet)
+            .and_then(|()| nb::block!(self.poll_send()));
+
+        if pa != original {
+            self.set_pa_level(original)?;
+        }
+        sent
+    }
+
+    fn send_with_pa_escalation(&mut self, packet: &[u8]) -> Result<Option<PALevel>, Self::Error> {
+        let original = self.nrf_config.pa_level;
+        let mut level = original;
+        let result = loop {
+            let sent = self
+                .send(packet)
+                .and_then(|()| nb::block!(self.poll_send()));
+            match sent {
+                Ok(true) => break Ok(Some(level)),
+                Ok(false) => {}
+                Err(err) => break Err(err),
+            }
+
+            level = match level {
+                PALevel::PA18dBm => PALevel::PA12dBm,
+                PALevel::PA12dBm => PALevel::PA6dBm,
+                PALevel::PA6dBm => PALevel::PA0dBm,
+                PALevel::PA0dBm => break Ok(None),
+            };
+            if let Err(err) = self.set_pa_level(level) {
+                break Err(err);
+            }
+        };
+
+        if self.nrf_config.pa_level != original {
+            self.set_pa_level(original)?;
+        }
+        result
+    }
+
+    fn send_adaptive(&mut self, packet: &[u8], keep_working_rate: bool) -> Result<(DataRate, bool), Self::Error> {
+        let preferred = self.nrf_config.data_rate;
+        let mut rate = preferred;
+        let result = loop {
+            let sent = self
+                .send(packet)
+                .and_then(|()| nb::block!(self.poll_send()));
+            match sent {
+                Ok(true) => break Ok((rate, true)),
+                Ok(false) => {}
+                Err(err) => break Err(err),
+            }
+
+            rate = match rate {
+                DataRate::R2Mbps => DataRate::R1Mbps,
+                DataRate::R1Mbps if cfg!(feature = "nrf24l01-plus") => DataRate::R250Kbps,
+                _ => break Ok((rate, false)),
+            };
+            if let Err(err) = self.set_data_rate(rate) {
+                break Err(err);
+            }
+        };
+
+        let keep = keep_working_rate && matches!(result, Ok((_, true)));
+        if !keep && self.nrf_config.data_rate != preferred {
+            self.set_data_rate(preferred)?;
+        }
+        result
+    }
+
+    fn measure_rtt(&mut self, packet: &[u8], timer: &mut impl Monotonic) -> Result<Option<u32>, Self::Error> {
+        let start = timer.now_us();
+        self.send(packet)?;
+        let success = nb::block!(self.poll_send())?;
+        let elapsed = timer.now_us().wrapping_sub(start);
+        Ok(if success { Some(elapsed) } else { None })
+    }
+
+    fn send_tracked(&mut self, packet: &[u8]) -> Result<SendToken, Self::Error> {
+        self.send(packet)?;
+
+        let token = SendToken(self.tx_next_token);
+        self.tx_next_token = self.tx_next_token.wrapping_add(1);
+        self.tx_in_flight = self.tx_in_flight.saturating_add(1);
+        Ok(token)
+    }
+
+    fn throughput_test(&mut self, payload: &[u8], count: u32, timer: &mut impl Monotonic) -> Result<ThroughputResult, Self::Error> {
+        let mut result = ThroughputResult {
+            sent: 0,
+            succeeded: 0,
+            failed: 0,
+            elapsed_us: 0,
+        };
+
+        let start = timer.now_us();
+        while result.succeeded + result.failed < count {
+            if result.sent < count {
+                match self.send_tracked(payload) {
+                    Ok(_) => {
+                        result.sent += 1;
+                        continue;
+                    }
+                    // Wait for a slot to free up
+                    Err(Error::TxFifoFull) => {}
+                    Err(err) => return Err(err),
+                }
+            }
+
+            match self.poll_completion()? {
+                Some(completion) if completion.success => result.succeeded += 1,
+                Some(_) => result.failed += 1,
+                None => {}
+            }
+        }
+        result.elapsed_us = timer.now_us().wrapping_sub(start);
+
+        // Can save power now
+        self.ce_disable();
+        Ok(result)
+    }
+
+    fn poll_completion(&mut self) -> Result<Option<Completion>, Self::Error> {
+        let oldest = SendToken(
+            self.tx_next_token
+                .wrapping_sub(self.tx_in_flight)
+                .wrapping_sub(self.tx_flushed),
+        );
+
+        // Packets flushed after an earlier MAX_RT are reported first
+        if self.tx_flushed > 0 {
+            self.tx_flushed -= 1;
+            return Ok(Some(Completion { token: oldest, success: false, retransmits: None }));
+        }
+
+        if self.tx_in_flight == 0 {
+            return Ok(None);
+        }
+
+        if self.mode != Mode::Tx {
+            self.to_tx()?;
+        }
+
+        let (status, fifo_status) = self.read_register::<FifoStatus>()?;
+        let completion = if status.tx_ds() {
+            // Report the success first, MAX_RT (if also set) belongs to a later packet
+            let retransmits = if status.max_rt() {
+                None
+            } else {
+                Some(self.observe()?.arc_cnt())
+            };
+            self.clear_flags(|flags| flags.set_tx_ds(true))?;
+            self.tx_in_flight -= 1;
+            Completion { token: oldest, success: true, retransmits }
+        } else if status.max_rt() {
+            let retransmits = Some(self.observe()?.arc_cnt());
+            self.reset_tx()?;
+            self.tx_flushed = self.tx_in_flight - 1;
+            self.tx_in_flight = 0;
+            Completion { token: oldest, success: false, retransmits }
+        } else if fifo_status.tx_empty() {
+            // A completion that coalesced with an earlier TX_DS
+            self.tx_in_flight -= 1;
+            Completion { token: oldest, success: true, retransmits: None }
+        } else {
+            self.ce_enable();
+            return Ok(None);
+        };
+
+        if self.tx_in_flight == 0 {
+            // Can save power now
+            self.ce_disable();
+        }
+        Ok(Some(completion))
+    }
+
     fn poll_send(&mut self) -> nb::Result<bool, Self::Error> {
         if self.mode != Mode::Tx {
             if let Err(err) = self.to_tx() {
@@ -402,9 +1434,13 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
             // the FIFO, we end up in an infinite loop
             self.send_command(&FlushTx)?;
             self.clear_tx_interrupts_and_ce()?;
+            self.forget_tracked();
+            self.last_send_ok = Some(false);
             Ok(false)
         } else if fifo_status.tx_empty() {
             self.clear_tx_interrupts_and_ce()?;
+            self.forget_tracked();
+            self.last_send_ok = Some(true);
             Ok(true)
         } else {
             self.ce_enable();
@@ -412,6 +1448,24 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
         }
     }
 
+    fn read_ack_payload(&mut self) -> Result<Option<Payload>, Self::Error> {
+        let payload = self.read_rx_fifo()?;
+        if payload.is_some() {
+            self.clear_flags(|flags| flags.set_rx_dr(true))?;
+        }
+        Ok(payload.map(|(_, payload)| payload))
+    }
+
+    fn ack_payload_available(&mut self) -> Result<bool, Self::Error> {
+        // STATUS is clocked out along with the read of FIFO_STATUS
+        let (status, fifo_status) = self.read_register::<FifoStatus>()?;
+        Ok(status.rx_dr() && !fifo_status.rx_empty())
+    }
+
+    fn last_send_succeeded(&self) -> Option<bool> {
+        self.last_send_ok
+    }
+
     fn clear_tx_interrupts_and_ce(&mut self) -> nb::Result<(), Self::Error> {
         if self.mode != Mode::Tx {
             if let Err(err) = self.to_tx() {
@@ -419,10 +1473,10 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
             }
         }
 
-        let mut clear = Status(0);
-        clear.set_tx_ds(true);
-        clear.set_max_rt(true);
-        self.write_register(clear)?;
+        self.clear_flags(|flags| {
+            flags.set_tx_ds(true);
+            flags.set_max_rt(true);
+        })?;
 
         // Can save power now
         self.ce_disable();
@@ -443,24 +1497,112 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
                 self.ce_enable();
             }
 
-            // TX won't continue while MAX_RT is set
             if status.max_rt() {
-                let mut clear = Status(0);
-                // If MAX_RT is set, the packet is not removed from the FIFO, so if we do not flush
-                // the FIFO, we end up in an infinite loop
-                self.send_command(&FlushTx)?;
-                // Clear TX interrupts
-                clear.set_tx_ds(true);
-                clear.set_max_rt(true);
-                self.write_register(clear)?;
+                self.reset_tx()?;
             }
         }
+        self.forget_tracked();
         // Can save power now
         self.ce_disable();
 
         Ok(())
     }
 
+    fn wait_empty_timeout(&mut self, delay: &mut impl DelayUs<u32>, timeout_us: u32) -> Result<bool, Self::Error> {
+        const POLL_INTERVAL_US: u32 = 10;
+
+        if self.mode != Mode::Tx {
+            self.to_tx()?;
+        }
+
+        let mut elapsed = 0;
+        loop {
+            let (status, fifo_status) = self.read_register::<FifoStatus>()?;
+            if fifo_status.tx_empty() {
+                self.forget_tracked();
+                break;
+            }
+
+            if status.max_rt() {
+                self.reset_tx()?;
+                continue;
+            }
+
+            if elapsed >= timeout_us {
+                self.ce_disable();
+                return Ok(false);
+            }
+            self.ce_enable();
+            delay.delay_us(POLL_INTERVAL_US);
+            elapsed = elapsed.saturating_add(POLL_INTERVAL_US);
+        }
+        // Can save power now
+        self.ce_disable();
+
+        Ok(true)
+    }
+
+    fn send_and_observe(&mut self, packet: &[u8]) -> Result<(bool, registers::ObserveTx), Self::Error> {
+        self.send(packet)?;
+        let success = nb::block!(self.poll_send())?;
+        let observe_tx = self.observe()?;
+        Ok((success, observe_tx))
+    }
+
+    fn send_counting(&mut self, packet: &[u8]) -> Result<(bool, u8), Self::Error> {
+        let (success, observe_tx) = self.send_and_observe(packet)?;
+        Ok((success, 1 + observe_tx.arc_cnt()))
+    }
+
+    fn send_realtime(&mut self, packet: &[u8]) -> Result<bool, Self::Error> {
+        let (_, previous) = self.read_register::<SetupRetr>()?;
+        self.configure_in_standby(|device| {
+            let mut single_attempt = previous.clone();
+            single_attempt.set_arc(0);
+            device.write_register(single_attempt)?;
+            Ok(())
+        })?;
+
+        let sent = self.send(packet).and_then(|()| nb::block!(self.poll_send()));
+
+        // Restore the retransmits even if sending failed
+        let restored = self.configure_in_standby(|device| device.write_register(previous).map(|_| ()));
+        sent.and_then(|success| restored.map(|()| success))
+    }
+
+    fn link_margin(&mut self, sample_sends: u8, payload: &[u8]) -> Result<LinkMargin, Self::Error> {
+        if sample_sends == 0 {
+            return Ok(LinkMargin { average_retransmits: 0.0, success_rate: 0.0, bars: 0 });
+        }
+
+        let mut succeeded = 0u32;
+        let mut retransmits = 0u32;
+        for _ in 0..sample_sends {
+            self.reset_tx()?;
+
+            let (success, attempts) = self.send_counting(payload)?;
+            if success {
+                succeeded += 1;
+            }
+            retransmits += u32::from(attempts - 1);
+        }
+
+        let average_retransmits = retransmits as f32 / f32::from(sample_sends);
+        let success_rate = succeeded as f32 / f32::from(sample_sends);
+        let bars = if success_rate < 0.5 {
+            0
+        } else if success_rate < 0.9 {
+            1
+        } else if average_retransmits >= 2.0 {
+            2
+        } else if average_retransmits > 0.5 {
+            3
+        } else {
+            4
+        };
+        Ok(LinkMargin { average_retransmits, success_rate, bars })
+    }
+
     fn observe(&mut self) -> Result<registers::ObserveTx, Self::Error> {
         if self.mode != Mode::Tx {
             self.to_tx()?;
@@ -469,6 +1611,19 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
         let (_, observe_tx) = self.read_register()?;
         Ok(observe_tx)
     }
+
+    fn lost_packets_since_reset(&mut self) -> Result<u8, Self::Error> {
+        let (_, observe_tx) = self.read_register::<registers::ObserveTx>()?;
+        Ok(observe_tx.plos_cnt())
+    }
+
+    fn reset_lost_packet_counter(&mut self) -> Result<(), Self::Error> {
+        self.configure_in_standby(|device| {
+            let (_, rf_ch) = device.read_register::<RfCh>()?;
+            device.write_register(rf_ch)?;
+            Ok(())
+        })
+    }
 }
 
 impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: SpiTransfer<u8, Error = SPIE>, SPIE: Debug> NRF24L01Configuration<'a>
@@ -483,72 +1638,89 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
 
     fn flush_tx(&mut self) -> Result<(), Self::Error> {
         self.send_command(&FlushTx)?;
+        self.forget_tracked();
         Ok(())
     }
 
     fn set_rf_channel(&mut self, rf_channel: u8) -> Result<(), Self::Error> {
         assert!(rf_channel < 126);
 
-        let mut register = RfCh(0);
-        register.set_rf_ch(rf_channel);
-        self.write_register(register)?;
+        self.configure_in_standby(|device| {
+            let mut register = RfCh(0);
+            register.set_rf_ch(rf_channel);
+            device.write_register(register)?;
 
-        self.nrf_config.rf_channel = rf_channel;
+            device.nrf_config.rf_channel = rf_channel;
 
-        Ok(())
+            Ok(())
+        })
     }
 
     fn set_data_rate(&mut self, rate: DataRate) -> Result<(), Self::Error> {
-        let power_level = &self.nrf_config.pa_level;
-
-        let mut register = RfSetup(0);
-        register.set_rf_pwr(match power_level {
-            PALevel::PA0dBm => 3,
-            PALevel::PA6dBm => 2,
-            PALevel::PA12dBm => 1,
-            PALevel::PA18dBm => 0,
-        });
-
-        let (dr_low, dr_high) = match rate {
-            DataRate::R250Kbps => (true, false),
-            DataRate::R1Mbps => (false, false),
-            DataRate::R2Mbps => (false, true),
-        };
-        register.set_rf_dr_low(dr_low);
-        register.set_rf_dr_high(dr_high);
+        Self::check_data_rate(rate)?;
+        self.configure_in_standby(|device| {
+            device.update_register::<RfSetup, _, _>(|register| register.set_data_rate(rate))?;
+
+            if rate == DataRate::R250Kbps {
+                // RF_DR_LOW is reserved on the original nRF24L01 and doesn't stick there
+                let (_, rf_setup) = device.read_register::<RfSetup>()?;
+                if !rf_setup.rf_dr_low() {
+                    let previous = device.nrf_config.data_rate;
+                    device.update_register::<RfSetup, _, _>(|register| register.set_data_rate(previous))?;
+                    return Err(Error::UnsupportedDataRate);
+                }
+            }
 
-        self.write_register(register)?;
+            device.nrf_config.data_rate = rate;
+            Ok(())
+        })
+    }
 
-        self.nrf_config.data_rate = rate;
-        Ok(())
+    fn set_data_rate_checked(&mut self, rate: DataRate) -> Result<(), Self::Error> {
+        let channel = self.nrf_config.rf_channel;
+        if rate == DataRate::R2Mbps && (channel % 2 == 1 || !(2..=123).contains(&channel)) {
+            return Err(Error::ChannelBandwidthConflict);
+        }
+        self.set_data_rate(rate)
     }
 
     fn set_pa_level(&mut self, power: config::PALevel) -> Result<(), Self::Error> {
-        let data_rate = &self.nrf_config.data_rate;
-
-        let mut register = RfSetup(0);
-        register.set_rf_pwr(match power {
-            PALevel::PA0dBm => 3,
-            PALevel::PA6dBm => 2,
-            PALevel::PA12dBm => 1,
-            PALevel::PA18dBm => 0,
-        });
+        self.configure_in_standby(|device| {
+            device.update_register::<RfSetup, _, _>(|register| register.set_pa_level(power))?;
+            device.nrf_config.pa_level = power;
+            Ok(())
+        })
+    }
 
-        let (dr_low, dr_high) = match data_rate {
-            DataRate::R250Kbps => (true, false),
-            DataRate::R1Mbps => (false, false),
-            DataRate::R2Mbps => (false, true),
-        };
-        register.set_rf_dr_low(dr_low);
-        register.set_rf_dr_high(dr_high);
+    fn set_rf_setup(&mut self, rate: DataRate, power: PALevel) -> Result<(), Self::Error> {
+        Self::check_data_rate(rate)?;
+        self.configure_in_standby(|device| {
+            device.update_register::<RfSetup, _, _>(|register| {
+                register.set_data_rate(rate);
+                register.set_pa_level(power);
+            })?;
+            device.nrf_config.data_rate = rate;
+            device.nrf_config.pa_level = power;
+            Ok(())
+        })
+    }
 
-        self.write_register(register)?;
+    fn set_lna_gain(&mut self, high: bool) -> Result<(), Self::Error> {
+        // LNA_HCURR is reserved on the nRF24L01+
+        if cfg!(feature = "nrf24l01-plus") {
+            return Err(Error::UnsupportedOnVariant);
+        }
 
-        self.nrf_config.pa_level = power;
-        Ok(())
+        self.configure_in_standby(|device| {
+            device.update_register::<RfSetup, _, _>(|register| register.set_lna_gain(high))
+        })
     }
 
     fn set_crc_mode(&mut self, mode: CrcMode) -> Result<(), Self::Error> {
+        if mode == CrcMode::Disabled && self.nrf_config.auto_ack_pipes.iter().any(|auto_ack| *auto_ack) {
+            return Err(Error::CrcRequiredForAutoAck);
+        }
+
         match self.update_config(|config| {
             let (en_crc, crco) = match mode {
                 CrcMode::Disabled => (false, false),
@@ -580,112 +1752,593 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
         }
     }
 
+    fn enable_interrupts(&mut self, rx: bool, tx: bool, max_rt: bool) -> Result<(), Self::Error> {
+        self.set_interrupt_mask(config::InterruptMask {
+            data_ready_rx: !rx,
+            data_sent_tx: !tx,
+            max_retramsits_tx: !max_rt,
+        })
+    }
+
+    fn mask_interrupt(&mut self, which: config::InterruptKind, masked: bool) -> Result<(), Self::Error> {
+        self.update_config(|config| match which {
+            config::InterruptKind::RxDataReady => config.set_mask_rx_dr(masked),
+            config::InterruptKind::TxDataSent => config.set_mask_tx_ds(masked),
+            config::InterruptKind::MaxRetransmit => config.set_mask_max_rt(masked),
+        })?;
+
+        let interrupt_mask = &mut self.nrf_config.interrupt_mask;
+        match which {
+            config::InterruptKind::RxDataReady => interrupt_mask.data_ready_rx = masked,
+            config::InterruptKind::TxDataSent => interrupt_mask.data_sent_tx = masked,
+            config::InterruptKind::MaxRetransmit => interrupt_mask.max_retramsits_tx = masked,
+        }
+        Ok(())
+    }
+
     fn set_read_enabled_pipes(&mut self, read_enabled_pipes: &[bool; PIPES_COUNT]) -> Result<(), Self::Error> {
-        match self.write_register(EnRxaddr::from_bools(read_enabled_pipes)) {
-            Ok(_) => {
-                self.nrf_config.read_enabled_pipes = *read_enabled_pipes;
-                Ok(())
-            },
-            Err(err) => Err(err),
+        if let Some((pipe, len)) = invalid_payload_length(&self.nrf_config.pipe_payload_lengths, read_enabled_pipes) {
+            return Err(Error::InvalidPayloadLength { pipe, len });
         }
+
+        self.configure_in_standby(|device| {
+            match device.write_register(EnRxaddr::from_bools(read_enabled_pipes)) {
+                Ok(_) => {
+                    device.nrf_config.read_enabled_pipes = *read_enabled_pipes;
+                    Ok(())
+                },
+                Err(err) => Err(err),
+            }
+        })
     }
 
     fn set_rx_addrs(&mut self, pipe_no: usize, addr: &'a [u8]) -> Result<(), Self::Error> {
-        macro_rules! w {
-            ( $($no: expr, $name: ident);+ ) => (
-                match pipe_no {
-                    $(
-                        $no => {
-                            use crate::registers::$name;
-                            let register = $name::new(addr);
-                            self.write_register(register)?;
-                        }
-                    )+
-                        _ => panic!("No such pipe {}", pipe_no)
+        // Pipes 2-5 only use the LSB
+        let min_len = if pipe_no < 2 { MIN_ADDR_BYTES } else { 1 };
+        if !(min_len..=MAX_ADDR_BYTES).contains(&addr.len()) {
+            return Err(Error::InvalidAddressLength { got: addr.len() });
+        }
+
+        self.configure_in_standby(|device| {
+            match pipe_no {
+                0 => {
+                    use crate::registers::RxAddrP0;
+                    device.write_register(RxAddrP0::new(addr))?;
                 }
-            )
+                1 => {
+                    use crate::registers::RxAddrP1;
+                    device.write_register(RxAddrP1::new(addr))?;
+                }
+                // Pipes 2-5 share all but the LSB with pipe 1
+                _ => {
+                    device.write_rx_addr_lsb(pipe_no, addr[0])?;
+                    device.rx_addr_lsbs[pipe_no] = addr[0];
+                }
+            }
+
+            device.nrf_config.rx_addrs[pipe_no] = addr;
+            Ok(())
+        })
+    }
+
+    fn set_pipe_rx_addr(&mut self, pipe: Pipe, addr: &'a [u8]) -> Result<(), Self::Error> {
+        self.set_rx_addrs(pipe.index(), addr)
+    }
+
+    fn set_rx_addr_lsb(&mut self, pipe: Pipe, lsb: u8) -> Result<(), Self::Error> {
+        let pipe_no = pipe.index();
+        if pipe_no < 2 {
+            // Pipes 0 and 1 need a full address
+            return Err(Error::InvalidAddressLength { got: 1 });
         }
-        w!(0, RxAddrP0;
-           1, RxAddrP1;
-           2, RxAddrP2;
-           3, RxAddrP3;
-           4, RxAddrP4;
-           5, RxAddrP5);
 
-        self.nrf_config.rx_addrs[pipe_no] = addr;
-        Ok(())
+        self.configure_in_standby(|device| {
+            device.write_rx_addr_lsb(pipe_no, lsb)?;
+            device.rx_addr_lsbs[pipe_no] = lsb;
+            Ok(())
+        })
     }
 
     fn set_tx_addr(&mut self, addr: &'a [u8]) -> Result<(), Self::Error> {
-        let register = TxAddr::new(addr);
-        self.write_register(register)?;
-        self.nrf_config.tx_addr = addr;
-        Ok(())
+        if !(MIN_ADDR_BYTES..=MAX_ADDR_BYTES).contains(&addr.len()) {
+            return Err(Error::InvalidAddressLength { got: addr.len() });
+        }
+
+        self.configure_in_standby(|device| {
+            let register = TxAddr::new(addr);
+            device.write_register(register)?;
+            device.nrf_config.tx_addr = addr;
+            if device.pipe0_saved.is_some() {
+                device.set_rx_addrs(0, addr)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn use_pipe0_for_ack(&mut self, enable: bool) -> Result<(), Self::Error> {
+        if enable {
+            if self.pipe0_saved.is_none() {
+                self.pipe0_saved = Some(self.nrf_config.rx_addrs[0]);
+            }
+            self.set_rx_addrs(0, self.nrf_config.tx_addr)
+        } else if let Some(addr) = self.pipe0_saved.take() {
+            self.set_rx_addrs(0, addr)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn set_rx_address<const N: usize>(&mut self, pipe_no: usize, addr: &'a Address<N>) -> Result<(), Self::Error> {
+        self.set_rx_addrs(pipe_no, addr.as_bytes())
+    }
+
+    fn set_tx_address<const N: usize>(&mut self, addr: &'a Address<N>) -> Result<(), Self::Error> {
+        self.set_tx_addr(addr.as_bytes())
     }
 
     fn set_retransmit_config(&mut self, delay: u8, count: u8) -> Result<(), Self::Error> {
-        let mut register = SetupRetr(0);
-        register.set_ard(delay);
-        register.set_arc(count);
-        self.write_register(register)?;
-        self.nrf_config.retransmit_config = RetransmitConfig { delay, count };
-        Ok(())
+        self.configure_in_standby(|device| {
+            let mut register = SetupRetr(0);
+            register.set_ard(delay);
+            register.set_arc(count);
+            device.write_register(register)?;
+            device.nrf_config.retransmit_config = RetransmitConfig { delay, count };
+            Ok(())
+        })
+    }
+
+    fn set_auto_retransmit_delay_auto(&mut self, count: u8) -> Result<(), Self::Error> {
+        let (_, feature) = self.read_register::<Feature>()?;
+        let ack_payload = if feature.en_ack_pay() { MAX_PAYLOAD_BYTES as u8 } else { 0 };
+        // CRC is forced on while auto-ack is enabled
+        let crc = match self.nrf_config.crc_mode {
+            CrcMode::Disabled => CrcMode::OneByte,
+            crc => crc,
+        };
+        // The ACK is an Enhanced ShockBurst packet, so it always has a packet control field
+        let data_rate = self.nrf_config.data_rate;
+        let air_time_us = timing::air_time_us(ack_payload, data_rate, self.nrf_config.address_width, crc, true);
+        let min_us = if data_rate == DataRate::R250Kbps { 500 } else { 250 };
+        let delay_us = (timing::STANDBY_TO_ACTIVE_US + air_time_us).max(min_us);
+        // ARD encodes 250µs * (ard + 1)
+        let ard = (delay_us.div_ceil(250) - 1).min(15) as u8;
+        self.set_retransmit_config(ard, count)
     }
 
     fn set_auto_ack(&mut self, auto_ack_pipes: [bool; PIPES_COUNT]) -> Result<(), Self::Error> {
-        let register = EnAa::from_bools(&auto_ack_pipes);
-        self.write_register(register)?;
-        self.nrf_config.auto_ack_pipes = auto_ack_pipes;
-        Ok(())
+        self.configure_in_standby(|device| {
+            let register = EnAa::from_bools(&auto_ack_pipes);
+            device.write_register(register)?;
+            device.nrf_config.auto_ack_pipes = auto_ack_pipes;
+            Ok(())
+        })
+    }
+
+    fn set_pipe_auto_ack(&mut self, pipe: Pipe, enabled: bool) -> Result<(), Self::Error> {
+        let mut auto_ack_pipes = self.nrf_config.auto_ack_pipes;
+        auto_ack_pipes[pipe.index()] = enabled;
+        self.set_auto_ack(auto_ack_pipes)
+    }
+
+    fn set_legacy_shockburst(&mut self) -> Result<(), Self::Error> {
+        self.configure_in_standby(|device| {
+            // Auto-ack goes first, so CRC can be disabled along with it
+            device.set_auto_ack([false; PIPES_COUNT])?;
+            device.set_crc_mode(CrcMode::Disabled)?;
+            let delay = device.nrf_config.retransmit_config.delay;
+            device.set_retransmit_config(delay, 0)?;
+
+            for pipe in Pipe::ALL {
+                device.set_pipe_dynamic_payload(pipe, false)?;
+            }
+            device.update_register::<Feature, _, _>(|feature| {
+                feature.set_en_dpl(false);
+                feature.set_en_ack_pay(false);
+            })
+        })
+    }
+
+    fn set_broadcast_mode(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        if enabled {
+            if self.broadcast_saved.is_none() {
+                self.broadcast_saved = Some((self.nrf_config.retransmit_config, self.nrf_config.auto_ack_pipes));
+            }
+            self.set_retransmit_config(0, 0)?;
+            self.set_auto_ack([false; PIPES_COUNT])
+        } else if let Some((retransmit_config, auto_ack_pipes)) = self.broadcast_saved {
+            self.set_retransmit_config(retransmit_config.delay, retransmit_config.count)?;
+            self.set_auto_ack(auto_ack_pipes)?;
+            self.broadcast_saved = None;
+            Ok(())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn minimize_power(&mut self) -> Result<(), Self::Error> {
+        let read_enabled_pipes = self.nrf_config.read_enabled_pipes;
+        self.set_read_enabled_pipes(&read_enabled_pipes)?;
+
+        let mut auto_ack_pipes = self.nrf_config.auto_ack_pipes;
+        for (auto_ack, enabled) in auto_ack_pipes.iter_mut().zip(read_enabled_pipes.iter()) {
+            *auto_ack &= *enabled;
+        }
+        self.set_auto_ack(auto_ack_pipes)?;
+
+        // Without RX pipes nothing is received, without auto-ack on pipe 0 no ACK is awaited
+        let mut interrupt_mask = self.nrf_config.interrupt_mask;
+        interrupt_mask.data_ready_rx |= !read_enabled_pipes.iter().any(|enabled| *enabled);
+        interrupt_mask.max_retramsits_tx |= !auto_ack_pipes[0];
+        self.set_interrupt_mask(interrupt_mask)
+    }
+
+    fn configure_as_transmitter(&mut self, tx_addr: &'a [u8], data_rate: DataRate, pa: PALevel, channel: u8) -> Result<(), Self::Error> {
+        self.to_standby()?;
+        self.set_rf_setup(data_rate, pa)?;
+        self.set_rf_channel(channel)?;
+        self.set_address_width(tx_addr.len() as u8)?;
+        self.set_tx_addr(tx_addr)?;
+        // ACKs are received on pipe 0
+        self.set_rx_addrs(0, tx_addr)?;
+        let mut read_enabled_pipes = self.nrf_config.read_enabled_pipes;
+        read_enabled_pipes[0] = true;
+        self.set_read_enabled_pipes(&read_enabled_pipes)?;
+        let mut auto_ack_pipes = self.nrf_config.auto_ack_pipes;
+        auto_ack_pipes[0] = true;
+        self.set_auto_ack(auto_ack_pipes)?;
+        if self.nrf_config.crc_mode == CrcMode::Disabled {
+            self.set_crc_mode(CrcMode::TwoBytes)?;
+        }
+        // 1500μs delay, 15 retransmits
+        self.set_retransmit_config(5, 15)?;
+        let pipe_payload_lengths = self.nrf_config.pipe_payload_lengths;
+        self.set_pipes_payload_lengths(pipe_payload_lengths)
+    }
+
+    fn configure_as_receiver(&mut self, rx_addr: &'a [u8], data_rate: DataRate, pa: PALevel, channel: u8) -> Result<(), Self::Error> {
+        self.to_standby()?;
+        self.set_rf_setup(data_rate, pa)?;
+        self.set_rf_channel(channel)?;
+        self.set_address_width(rx_addr.len() as u8)?;
+        self.set_rx_addrs(1, rx_addr)?;
+        let mut read_enabled_pipes = self.nrf_config.read_enabled_pipes;
+        read_enabled_pipes[1] = true;
+        self.set_read_enabled_pipes(&read_enabled_pipes)?;
+        let mut auto_ack_pipes = self.nrf_config.auto_ack_pipes;
+        auto_ack_pipes[1] = true;
+        self.set_auto_ack(auto_ack_pipes)?;
+        if self.nrf_config.crc_mode == CrcMode::Disabled {
+            self.set_crc_mode(CrcMode::TwoBytes)?;
+        }
+        let pipe_payload_lengths = self.nrf_config.pipe_payload_lengths;
+        self.set_pipes_payload_lengths(pipe_payload_lengths)
     }
 
     fn set_address_width(&mut self, width: u8) -> Result<(), Self::Error> {
-        let register = SetupAw(width - 2);
-        self.write_register(register)?;
-        self.nrf_config.address_width = width;
-        Ok(())
+        if !(MIN_ADDR_WIDTH..=MAX_ADDR_BYTES).contains(&usize::from(width)) {
+            return Err(Error::InvalidConfig(ConfigError::InvalidAddressWidth(width)));
+        }
+
+        self.configure_in_standby(|device| {
+            let register = SetupAw(width - 2);
+            device.write_register(register)?;
+            device.nrf_config.address_width = width;
+            Ok(())
+        })
     }
 
     fn set_pipes_payload_lengths(&mut self, lengths: [Option<u8>; PIPES_COUNT]) -> Result<(), Self::Error> {
-        let mut bools = [true; PIPES_COUNT];
-        for (i, len) in lengths.iter().enumerate() {
-            bools[i] = len.is_none();
+        // Validate everything before writing so a bad entry doesn't leave the chip half-configured
+        if let Some((pipe, len)) = invalid_payload_length(&lengths, &self.nrf_config.read_enabled_pipes) {
+            return Err(Error::InvalidPayloadLength { pipe, len });
         }
-        let dynpd = Dynpd::from_bools(&bools);
-        if dynpd.0 != 0 {
-            self.update_register::<Feature, _, _>(|feature| {
+
+        self.write_payload_lengths(lengths)
+    }
+
+    fn set_pipe_dynamic_payload(&mut self, pipe: Pipe, dynamic: bool) -> Result<(), Self::Error> {
+        let pipe = pipe.index();
+
+        self.configure_in_standby(|device| {
+            if dynamic {
+                device.update_register::<Feature, _, _>(|feature| {
+                    feature.set_en_dpl(true);
+                })?;
+            }
+            device.update_register::<Dynpd, _, _>(|dynpd| {
+                dynpd.set_dpl_p(pipe, dynamic);
+            })?;
+
+            device.nrf_config.pipe_payload_lengths[pipe] = if dynamic {
+                None
+            } else {
+                Some(device.read_rx_pw(pipe)?)
+            };
+            Ok(())
+        })
+    }
+
+    fn enable_dynamic_payloads(&mut self, pipes: [bool; PIPES_COUNT]) -> Result<(), Self::Error> {
+        self.configure_in_standby(|device| {
+            device.update_register::<Feature, _, _>(|feature| {
                 feature.set_en_dpl(true);
             })?;
+            device.write_register(Dynpd::from_bools(&pipes))?;
+
+            for (pipe, dynamic) in pipes.iter().enumerate() {
+                device.nrf_config.pipe_payload_lengths[pipe] = if *dynamic {
+                    None
+                } else {
+                    Some(device.read_rx_pw(pipe)?)
+                };
+            }
+            Ok(())
+        })
+    }
+
+    fn disable_dynamic_payloads(&mut self) -> Result<(), Self::Error> {
+        self.configure_in_standby(|device| {
+            device.write_register(Dynpd(0))?;
+            device.update_register::<Feature, _, _>(|feature| {
+                feature.set_en_dpl(false);
+                feature.set_en_ack_pay(false);
+            })?;
+
+            for pipe in 0..PIPES_COUNT {
+                device.nrf_config.pipe_payload_lengths[pipe] = Some(device.read_rx_pw(pipe)?);
+            }
+            Ok(())
+        })
+    }
+
+    fn set_ack_payload_enabled(&mut self, enabled: bool) -> Result<(), Self::Error> {
+        self.configure_in_standby(|device| {
+            let (_, mut feature) = device.read_register::<Feature>()?;
+            if enabled && !feature.en_dpl() {
+                return Err(Error::DynamicPayloadRequired);
+            }
+            if feature.en_ack_pay() != enabled {
+                feature.set_en_ack_pay(enabled);
+                device.write_register(feature)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn is_ack_payload_enabled(&mut self) -> Result<bool, Self::Error> {
+        let (_, feature) = self.read_register::<Feature>()?;
+        Ok(feature.en_ack_pay())
+    }
+
+    fn enable_rx_pipe(&mut self, pipe: Pipe, addr: &'a [u8], payload: Option<u8>, auto_ack: bool) -> Result<(), Self::Error> {
+        let pipe_no = pipe.index();
+        let mut pipe_payload_lengths = self.nrf_config.pipe_payload_lengths;
+        pipe_payload_lengths[pipe_no] = payload;
+        let mut read_enabled_pipes = self.nrf_config.read_enabled_pipes;
+        read_enabled_pipes[pipe_no] = true;
+        if let Some((pipe, len)) = invalid_payload_length(&pipe_payload_lengths, &read_enabled_pipes) {
+            return Err(Error::InvalidPayloadLength { pipe, len });
         }
-        self.write_register(dynpd)?;
 
-        // Set static payload lengths
-        macro_rules! set_rx_pw {
-            ($name: ident, $index: expr) => {{
-                use crate::registers::$name;
-                let length = lengths[$index].unwrap_or(0);
-                let mut register = $name(0);
-                register.set(length);
-                self.write_register(register)?;
-            }};
-        }
-        set_rx_pw!(RxPwP0, 0);
-        set_rx_pw!(RxPwP1, 1);
-        set_rx_pw!(RxPwP2, 2);
-        set_rx_pw!(RxPwP3, 3);
-        set_rx_pw!(RxPwP4, 4);
-        set_rx_pw!(RxPwP5, 5);
+        self.configure_in_standby(|device| {
+            device.set_rx_addrs(pipe_no, addr)?;
 
-        self.nrf_config.pipe_payload_lengths = lengths;
+            match payload {
+                Some(len) => {
+                    device.write_rx_pw(pipe_no, len)?;
+                    device.update_register::<Dynpd, _, _>(|dynpd| {
+                        dynpd.set_dpl_p(pipe_no, false);
+                    })?;
+                    device.nrf_config.pipe_payload_lengths[pipe_no] = Some(len);
+                }
+                None => device.set_pipe_dynamic_payload(pipe, true)?,
+            }
 
-        Ok(())
+            let mut auto_ack_pipes = device.nrf_config.auto_ack_pipes;
+            auto_ack_pipes[pipe_no] = auto_ack;
+            device.set_auto_ack(auto_ack_pipes)?;
+
+            // Enable reception last, once the pipe is fully set up
+            device.set_read_enabled_pipes(&read_enabled_pipes)
+        })
+    }
+
+    fn disable_rx_pipe(&mut self, pipe: Pipe) -> Result<(), Self::Error> {
+        let pipe = pipe.index();
+
+        self.configure_in_standby(|device| {
+            let mut read_enabled_pipes = device.nrf_config.read_enabled_pipes;
+            read_enabled_pipes[pipe] = false;
+            device.set_read_enabled_pipes(&read_enabled_pipes)?;
+
+            let mut auto_ack_pipes = device.nrf_config.auto_ack_pipes;
+            auto_ack_pipes[pipe] = false;
+            device.set_auto_ack(auto_ack_pipes)
+        })
+    }
+
+    fn apply_pipe_profile(&mut self, profile: &PipeProfile<'a>) -> Result<(), Self::Error> {
+        for (pipe, addr) in profile.rx_addrs.iter().enumerate() {
+            // Pipes 2-5 only use the LSB
+            let min_len = if pipe < 2 { MIN_ADDR_BYTES } else { 1 };
+            if !(min_len..=MAX_ADDR_BYTES).contains(&addr.len()) {
+                return Err(Error::InvalidAddressLength { got: addr.len() });
+            }
+        }
+        if let Some((pipe, len)) = invalid_payload_length(&profile.pipe_payload_lengths, &profile.read_enabled_pipes) {
+            return Err(Error::InvalidPayloadLength { pipe, len });
+        }
+
+        let previous = PipeProfile {
+            read_enabled_pipes: self.nrf_config.read_enabled_pipes,
+            rx_addrs: self.nrf_config.rx_addrs,
+            auto_ack_pipes: self.nrf_config.auto_ack_pipes,
+            pipe_payload_lengths: self.nrf_config.pipe_payload_lengths,
+        };
+        let previous_lsbs = self.rx_addr_lsbs;
+        let applied = self.write_pipe_profile(profile);
+        if applied.is_err() {
+            // Best effort, the original error is what matters
+            let _ = self.configure_in_standby(|device| {
+                device.write_pipe_profile(&previous)?;
+                // The cached addresses of pipes 2-5 may predate an LSB set by value
+                for (pipe_no, lsb) in previous_lsbs.iter().enumerate().skip(2) {
+                    device.write_rx_addr_lsb(pipe_no, *lsb)?;
+                }
+                device.rx_addr_lsbs = previous_lsbs;
+                Ok(())
+            });
+        }
+        applied
+    }
+
+    fn set_bidirectional_addrs(&mut self, my_addr: &'a [u8], peer_addr: &'a [u8]) -> Result<(), Self::Error> {
+        self.configure_in_standby(|device| {
+            device.set_tx_addr(peer_addr)?;
+            device.set_rx_addrs(0, peer_addr)?;
+            device.set_rx_addrs(1, my_addr)?;
+
+            let mut read_enabled_pipes = device.nrf_config.read_enabled_pipes;
+            read_enabled_pipes[0] = true;
+            read_enabled_pipes[1] = true;
+            device.set_read_enabled_pipes(&read_enabled_pipes)
+        })
+    }
+
+    fn export_config(&mut self) -> Result<NRF24L01ConfigOwned, Self::Error> {
+        use crate::registers::{RxAddrP0, RxAddrP1};
+
+        let (_, config) = self.read_register::<Config>()?;
+        let (_, en_aa) = self.read_register::<EnAa>()?;
+        // Auto-ack forces CRC on, whatever EN_CRC says
+        let crc_mode = match (config.en_crc() || en_aa.0 != 0, config.crco()) {
+            (false, _) => CrcMode::Disabled,
+            (true, false) => CrcMode::OneByte,
+            (true, true) => CrcMode::TwoBytes,
+        };
+        let interrupt_mask = config::InterruptMask {
+            data_ready_rx: config.mask_rx_dr(),
+            data_sent_tx: config.mask_tx_ds(),
+            max_retramsits_tx: config.mask_max_rt(),
+        };
+
+        let (_, rf_setup) = self.read_register::<RfSetup>()?;
+        let data_rate = DataRate::from_register(&rf_setup)?;
+        let pa_level = PALevel::from_register(&rf_setup);
+        let (_, rf_ch) = self.read_register::<RfCh>()?;
+        let (_, setup_aw) = self.read_register::<SetupAw>()?;
+        let address_width = setup_aw.aw() + 2;
+        let (_, setup_retr) = self.read_register::<SetupRetr>()?;
+        let (_, en_rxaddr) = self.read_register::<EnRxaddr>()?;
+
+        let mut rx_addrs = [[0; MAX_ADDR_BYTES]; PIPES_COUNT];
+        let (_, rx_addr_p0) = self.read_register::<RxAddrP0>()?;
+        rx_addrs[0].copy_from_slice(rx_addr_p0.as_bytes());
+        let (_, rx_addr_p1) = self.read_register::<RxAddrP1>()?;
+        rx_addrs[1].copy_from_slice(rx_addr_p1.as_bytes());
+        macro_rules! read_lsb {
+            ( $($no: expr, $name: ident);+ ) => ($(
+                {
+                    use crate::registers::$name;
+                    rx_addrs[$no][0] = self.read_register::<$name>()?.1.0;
+                }
+            )+)
+        }
+        read_lsb!(2, RxAddrP2;
+                  3, RxAddrP3;
+                  4, RxAddrP4;
+                  5, RxAddrP5);
+        let mut tx_addr = [0; MAX_ADDR_BYTES];
+        let (_, tx_addr_register) = self.read_register::<TxAddr>()?;
+        tx_addr.copy_from_slice(tx_addr_register.as_bytes());
+
+        let (_, feature) = self.read_register::<Feature>()?;
+        let (_, dynpd) = self.read_register::<Dynpd>()?;
+        let mut read_enabled_pipes = en_rxaddr.to_bools();
+        let mut pipe_payload_lengths = [None; PIPES_COUNT];
+        for (pipe_no, len) in pipe_payload_lengths.iter_mut().enumerate() {
+            if !(feature.en_dpl() && dynpd.dpl_p(pipe_no)) {
+                let width = self.read_rx_pw(pipe_no)?;
+                // A static width of 0 means the pipe is unused, whatever EN_RXADDR says (as
+                // it does for pipes 0 and 1 after reset)
+                if width == 0 {
+                    read_enabled_pipes[pipe_no] = false;
+                }
+                *len = Some(width);
+            }
+        }
+
+        Ok(NRF24L01ConfigOwned {
+            data_rate,
+            crc_mode,
+            rf_channel: rf_ch.rf_ch(),
+            pa_level,
+            interrupt_mask,
+            read_enabled_pipes,
+            rx_addrs,
+            tx_addr,
+            retransmit_config: RetransmitConfig {
+                delay: setup_retr.ard(),
+                count: setup_retr.arc(),
+            },
+            auto_ack_pipes: en_aa.to_bools(),
+            address_width,
+            pipe_payload_lengths,
+            mode: self.mode,
+        })
+    }
+
+    fn import_config(&mut self, cfg: &'a NRF24L01ConfigOwned) -> Result<(), Self::Error> {
+        let configuration = cfg.as_config();
+        if let Some((pipe, len)) = invalid_payload_length(&configuration.pipe_payload_lengths, &configuration.read_enabled_pipes) {
+            return Err(Error::InvalidPayloadLength { pipe, len });
+        }
+
+        self.configure_in_standby(|device| {
+            device.set_data_rate(configuration.data_rate)?;
+            // Auto-ack goes first, so CRC can be disabled along with it
+            device.set_auto_ack(configuration.auto_ack_pipes)?;
+            device.set_crc_mode(configuration.crc_mode)?;
+            device.set_rf_channel(configuration.rf_channel)?;
+            device.set_pa_level(configuration.pa_level)?;
+            device.set_interrupt_mask(configuration.interrupt_mask)?;
+            device.set_address_width(configuration.address_width)?;
+            for (pipe_no, addr) in configuration.rx_addrs.iter().enumerate() {
+                device.set_rx_addrs(pipe_no, addr)?;
+            }
+            device.set_tx_addr(configuration.tx_addr)?;
+            device.set_retransmit_config(configuration.retransmit_config.delay, configuration.retransmit_config.count)?;
+            // The widths go first, enabling a pipe checks them
+            device.write_payload_lengths(configuration.pipe_payload_lengths)?;
+            device.set_read_enabled_pipes(&configuration.read_enabled_pipes)
+        })
+    }
+
+    fn configure(&mut self, cfg: &'a NRF24L01ConfigOwned) -> Result<(), Self::Error> {
+        let configuration = cfg.as_config();
+        configuration.validate().map_err(Error::InvalidConfig)?;
+        self.import_config(cfg)?;
+
+        match configuration.mode {
+            Mode::Tx => self.to_tx(),
+            Mode::Rx => self.to_rx(),
+            Mode::Standby => self.to_standby(),
+            Mode::PowerDown => self.to_power_down(),
+        }
     }
 
     fn set_nrf_configuration(&mut self, configuration: NRF24L01Config<'a>) -> Result<(), Self::Error> {
+        configuration.validate().map_err(Error::InvalidConfig)?;
+
         if configuration.data_rate != self.nrf_config.data_rate {
             self.set_data_rate(configuration.data_rate)?;
         }
 
+        // Auto-ack goes first, so CRC can be disabled along with it
+        if configuration.auto_ack_pipes != self.nrf_config.auto_ack_pipes {
+            self.set_auto_ack(configuration.auto_ack_pipes)?;
+        }
+
         if configuration.crc_mode != self.nrf_config.crc_mode {
             self.set_crc_mode(configuration.crc_mode)?;
         }
@@ -702,11 +2355,7 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
             self.set_interrupt_mask(configuration.interrupt_mask)?;
         }
 
-        if configuration.read_enabled_pipes != self.nrf_config.read_enabled_pipes {
-            self.set_read_enabled_pipes(&configuration.read_enabled_pipes)?;
-        }
-
-        if configuration.rx_addrs != self.nrf_config.rx_addrs {
+        if configuration.rx_addrs != self.get_rx_addrs() {
             for (pipe_no, addr) in configuration.rx_addrs.iter().enumerate() {
                 self.set_rx_addrs(pipe_no, addr)?;
             }
@@ -720,16 +2369,17 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
             self.set_retransmit_config(configuration.retransmit_config.delay, configuration.retransmit_config.count)?;
         }
 
-        if configuration.auto_ack_pipes != self.nrf_config.auto_ack_pipes {
-            self.set_auto_ack(configuration.auto_ack_pipes)?;
-        }
-
         if configuration.address_width != self.nrf_config.address_width {
             self.set_address_width(configuration.address_width)?;
         }
 
+        // The widths go first, enabling a pipe checks them. Both were validated together above.
         if configuration.pipe_payload_lengths != self.nrf_config.pipe_payload_lengths {
-            self.set_pipes_payload_lengths(configuration.pipe_payload_lengths)?;
+            self.write_payload_lengths(configuration.pipe_payload_lengths)?;
+        }
+
+        if configuration.read_enabled_pipes != self.nrf_config.read_enabled_pipes {
+            self.set_read_enabled_pipes(&configuration.read_enabled_pipes)?;
         }
 
         Ok(())
@@ -751,6 +2401,11 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
         self.nrf_config.pa_level
     }
 
+    fn read_rf_setup(&mut self) -> Result<(DataRate, PALevel), Self::Error> {
+        let (_, rf_setup) = self.read_register::<RfSetup>()?;
+        Ok((DataRate::from_register(&rf_setup)?, PALevel::from_register(&rf_setup)))
+    }
+
     fn get_interrupt_mask(&self) -> config::InterruptMask {
         self.nrf_config.interrupt_mask
     }
@@ -759,8 +2414,12 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
         self.nrf_config.read_enabled_pipes
     }
 
-    fn get_rx_addrs(&self) -> [&'a [u8]; PIPES_COUNT] {
-        self.nrf_config.rx_addrs
+    fn get_rx_addrs(&self) -> [&[u8]; PIPES_COUNT] {
+        let mut rx_addrs = self.nrf_config.rx_addrs;
+        for (addr, lsb) in rx_addrs.iter_mut().zip(self.rx_addr_lsbs.iter()).skip(2) {
+            *addr = core::slice::from_ref(lsb);
+        }
+        rx_addrs
     }
 
     fn get_tx_addr(&self) -> &'a [u8] {
@@ -783,7 +2442,308 @@ impl<'a, E: Debug, CE: OutputPin<Error = E>, CSN: OutputPin<Error = E>, SPI: Spi
         self.nrf_config.pipe_payload_lengths
     }
 
-    fn get_config(&self) -> NRF24L01Config {
-        self.nrf_config
+    fn get_config(&self) -> NRF24L01Config<'_> {
+        NRF24L01Config {
+            rx_addrs: self.get_rx_addrs(),
+            mode: self.mode,
+            ..self.nrf_config
+        }
+    }
+
+    fn snapshot_config(&self) -> NRF24L01ConfigOwned {
+        NRF24L01ConfigOwned::from(&self.get_config())
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use core::convert::TryFrom;
+
+    use crate::mock::{Ether, MockRadio};
+    use crate::registers::{RegisterAddress, RfCh};
+    use crate::timing;
+    use crate::{
+        ChangeModes, ConfigError, CrcMode, DataRate, Device, Error, Mode, NRF24L01Config, NRF24L01ConfigOwned, NRF24L01Configuration,
+        PALevel, Payload, Pipe, Rx, Tx, PIPES_COUNT,
+    };
+
+    #[test]
+    fn payload_length_over_32_is_rejected_before_writing() {
+        let ether = Ether::new();
+        let radio = MockRadio::new(&ether);
+        let mut device = radio.device().unwrap();
+
+        let result = device.set_pipes_payload_lengths([Some(8), Some(33), None, None, None, None]);
+        assert!(matches!(result, Err(Error::InvalidPayloadLength { pipe: 1, len: 33 })));
+        // The valid entry for pipe 0 wasn't written either
+        assert_eq!(radio.register(RegisterAddress::RxPwP0 as u8), 0);
+        assert_eq!(radio.register(RegisterAddress::RxPwP1 as u8), 0);
+    }
+
+    #[test]
+    fn pipe0_address_of_1_byte_is_rejected() {
+        let ether = Ether::new();
+        let mut device = MockRadio::new(&ether).device().unwrap();
+
+        let result = device.set_rx_addrs(0, &[0xA5]);
+        assert!(matches!(result, Err(Error::InvalidAddressLength { got: 1 })));
+    }
+
+    #[test]
+    fn pipe0_address_of_6_bytes_is_rejected() {
+        let ether = Ether::new();
+        let mut device = MockRadio::new(&ether).device().unwrap();
+
+        let result = device.set_rx_addrs(0, &[0xA5; 6]);
+        assert!(matches!(result, Err(Error::InvalidAddressLength { got: 6 })));
+        assert_eq!(device.get_config().rx_addrs[0], &[0xE7; 5]);
+    }
+
+    #[test]
+    fn payload_length_of_0_is_rejected_on_a_receiving_pipe() {
+        let ether = Ether::new();
+        let radio = MockRadio::new(&ether);
+        let mut device = radio.device().unwrap();
+        device.set_read_enabled_pipes(&[true, true, false, false, false, false]).unwrap();
+
+        let result = device.set_pipes_payload_lengths([Some(8), Some(0), None, None, None, None]);
+        assert!(matches!(result, Err(Error::InvalidPayloadLength { pipe: 1, len: 0 })));
+        assert_eq!(radio.register(RegisterAddress::Dynpd as u8), 0);
+    }
+
+    #[test]
+    fn pipe_with_a_width_of_0_cannot_be_enabled() {
+        let ether = Ether::new();
+        let radio = MockRadio::new(&ether);
+        let mut device = radio.device().unwrap();
+        device
+            .set_pipes_payload_lengths([Some(8), Some(8), Some(0), Some(0), Some(0), Some(0)])
+            .unwrap();
+
+        let result = device.set_read_enabled_pipes(&[true, true, true, false, false, false]);
+        assert!(matches!(result, Err(Error::InvalidPayloadLength { pipe: 2, len: 0 })));
+        assert_eq!(radio.register(RegisterAddress::EnRxaddr as u8), 0x03);
+    }
+
+    #[test]
+    fn payload_length_of_none_enables_dynamic_payloads() {
+        let ether = Ether::new();
+        let radio = MockRadio::new(&ether);
+        let mut device = radio.device().unwrap();
+
+        device
+            .set_pipes_payload_lengths([Some(8), None, Some(0), Some(0), Some(0), Some(0)])
+            .unwrap();
+        assert_eq!(radio.register(RegisterAddress::Dynpd as u8), 0b10);
+        assert_eq!(radio.register(RegisterAddress::Feature as u8) & 0b100, 0b100);
+        assert_eq!(radio.register(RegisterAddress::RxPwP0 as u8), 8);
+        assert_eq!(device.get_config().pipe_payload_lengths[1], None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn respond_requires_ack_payloads() {
+        let ether = Ether::new();
+        let mut rx = MockRadio::new(&ether).device().unwrap();
+        rx.configure_as_receiver(b"abcde", DataRate::R1Mbps, PALevel::PA0dBm, 10).unwrap();
+        rx.enable_dynamic_payloads([true; PIPES_COUNT]).unwrap();
+
+        let result = rx.respond(Pipe::P1, b"resp");
+        assert!(matches!(result, Err(Error::AckPayloadRequired)));
+    }
+
+    #[test]
+    fn respond_answers_with_the_next_ack() {
+        let ether = Ether::new();
+        let mut tx = MockRadio::new(&ether).device().unwrap();
+        let mut rx = MockRadio::new(&ether).device().unwrap();
+        tx.configure_as_transmitter(b"abcde", DataRate::R1Mbps, PALevel::PA0dBm, 10).unwrap();
+        rx.configure_as_receiver(b"abcde", DataRate::R1Mbps, PALevel::PA0dBm, 10).unwrap();
+        for device in [&mut tx, &mut rx] {
+            device.enable_dynamic_payloads([true; PIPES_COUNT]).unwrap();
+            device.set_ack_payload_enabled(true).unwrap();
+        }
+        rx.to_rx().unwrap();
+
+        // Nothing is staged yet, so the first request is acknowledged without a payload
+        tx.send_blocking(b"req1").unwrap();
+        assert!(tx.read_ack_payload().unwrap().is_none());
+
+        let (pipe, request) = rx.read_with_pipe().unwrap();
+        assert_eq!((pipe, &*request), (1, &b"req1"[..]));
+        rx.respond(Pipe::try_from(pipe).unwrap(), b"resp1").unwrap();
+
+        // The response rides on the ACK of the following request
+        tx.send_blocking(b"req2").unwrap();
+        let response = tx.read_ack_payload().unwrap().unwrap();
+        assert_eq!(&*response, b"resp1");
+    }
+
+    #[test]
+    fn configure_enters_the_mode_of_the_config() {
+        let ether = Ether::new();
+        let radio = MockRadio::new(&ether);
+        // A receiver keeping the reset value of EN_AA
+        let receiver = NRF24L01ConfigOwned::from(&NRF24L01Config {
+            crc_mode: CrcMode::TwoBytes,
+            read_enabled_pipes: [true, true, false, false, false, false],
+            auto_ack_pipes: [true; PIPES_COUNT],
+            pipe_payload_lengths: [Some(8); PIPES_COUNT],
+            mode: Mode::Rx,
+            ..NRF24L01Config::DEFAULT
+        });
+        let mut device = radio.device_with_config(NRF24L01Config::DEFAULT).unwrap();
+
+        device.configure(&receiver).unwrap();
+        assert_eq!(device.mode(), Mode::Rx);
+    }
+
+    #[test]
+    fn receiver_on_pipe_0_needs_no_matching_tx_address() {
+        let mut receiver = NRF24L01Config {
+            crc_mode: CrcMode::TwoBytes,
+            read_enabled_pipes: [true, false, false, false, false, false],
+            rx_addrs: [b"abcde", &[0xC2; 5], &[0xC3], &[0xC4], &[0xC5], &[0xC6]],
+            auto_ack_pipes: [true; PIPES_COUNT],
+            pipe_payload_lengths: [Some(8); PIPES_COUNT],
+            mode: Mode::Rx,
+            ..NRF24L01Config::DEFAULT
+        };
+        assert_eq!(receiver.validate(), Ok(()));
+
+        receiver.mode = Mode::Tx;
+        assert_eq!(receiver.validate(), Err(ConfigError::AckAddressMismatch));
+    }
+
+    #[test]
+    fn exported_config_keeps_the_mode() {
+        let ether = Ether::new();
+        let mut receiver = MockRadio::new(&ether).device().unwrap();
+        receiver.configure_as_receiver(b"abcde", DataRate::R1Mbps, PALevel::PA0dBm, 10).unwrap();
+        receiver.to_rx().unwrap();
+        let exported = receiver.export_config().unwrap();
+        assert_eq!(exported.mode, Mode::Rx);
+
+        let mut device = MockRadio::new(&ether).device_with_config(NRF24L01Config::DEFAULT).unwrap();
+        device.configure(&exported).unwrap();
+        assert_eq!(device.mode(), Mode::Rx);
+    }
+
+    #[test]
+    fn corrupt_width_is_flushed_instead_of_read() {
+        let ether = Ether::new();
+        let radio = MockRadio::new(&ether);
+        let mut device = radio.device().unwrap();
+
+        radio.inject_rx(1, &[0xA5; 33]);
+        assert!(matches!(device.read_with_pipe(), Err(Error::NoPacket)));
+        radio.inject_rx(1, &[0xA5; 33]);
+        assert!(device.receive().unwrap().is_none());
+        radio.inject_rx(0, &[0xA5; 33]);
+        assert!(device.read_ack_payload().unwrap().is_none());
+        radio.inject_rx(1, &[0xA5; 33]);
+        radio.inject_rx(1, &[0xA5; 4]);
+        assert_eq!(device.read_all(&mut [Payload::new(&[]), Payload::new(&[])]).unwrap(), 0);
+
+        // Each time the packets were flushed rather than left at the head of the RX FIFO
+        assert!(device.rx_queue_empty().unwrap());
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn multicast_to_more_than_16_addresses_is_rejected() {
+        let ether = Ether::new();
+        let mut device = MockRadio::new(&ether).device().unwrap();
+
+        let addrs: [&[u8]; 17] = [b"abcde"; 17];
+        let result = device.multicast(&addrs, b"ping");
+        assert!(matches!(result, Err(Error::TooManyAddresses(17))));
+    }
+
+    #[test]
+    fn rx_addr_lsb_is_copied_into_the_cache() {
+        let ether = Ether::new();
+        let radio = MockRadio::new(&ether);
+        let mut device = radio.device().unwrap();
+
+        for node_id in 1..=2u8 {
+            device.set_rx_addr_lsb(Pipe::P2, 0x10 + node_id).unwrap();
+        }
+        assert_eq!(radio.register(RegisterAddress::RxAddrP2 as u8), 0x12);
+        assert_eq!(device.get_rx_addrs()[2], &[0x12]);
+        assert_eq!(device.snapshot_config().rx_addrs[2][0], 0x12);
+    }
+
+    #[cfg(feature = "nrf24l01-plus")]
+    #[test]
+    fn lna_gain_is_unsupported_on_the_plus() {
+        let ether = Ether::new();
+        let radio = MockRadio::new(&ether);
+        let mut device = radio.device().unwrap();
+        let rf_setup = radio.register(RegisterAddress::RfSetup as u8);
+
+        assert!(matches!(device.set_lna_gain(false), Err(Error::UnsupportedOnVariant)));
+        assert_eq!(radio.register(RegisterAddress::RfSetup as u8), rf_setup);
+    }
+
+    #[test]
+    fn channel_occupancy_consumes_the_settling_time() {
+        struct Delay(u32);
+        impl embedded_hal::blocking::delay::DelayUs<u32> for Delay {
+            fn delay_us(&mut self, us: u32) {
+                self.0 += us;
+            }
+        }
+
+        let ether = Ether::new();
+        let mut device = MockRadio::new(&ether).device().unwrap();
+        let mut delay = Delay(0);
+        device.measure_channel_occupancy(1, &mut delay).unwrap();
+        assert_eq!(delay.0, timing::STANDBY_TO_ACTIVE_US + timing::CARRIER_DETECT_US);
+
+        // Nothing is left pending for the next wait
+        device.wait_ready(&mut delay).unwrap();
+        assert_eq!(delay.0, timing::STANDBY_TO_ACTIVE_US + timing::CARRIER_DETECT_US);
+    }
+
+    #[test]
+    fn rx_pipe_setup_is_validated_and_keeps_rx_mode() {
+        let ether = Ether::new();
+        let radio = MockRadio::new(&ether);
+        let mut device = radio.device().unwrap();
+        device.set_pipes_payload_lengths([Some(8); PIPES_COUNT]).unwrap();
+        device.to_rx().unwrap();
+
+        let result = device.enable_rx_pipe(Pipe::P2, &[0xC3], Some(33), true);
+        assert!(matches!(result, Err(Error::InvalidPayloadLength { pipe: 2, len: 33 })));
+
+        device.enable_rx_pipe(Pipe::P2, &[0xC3], Some(8), true).unwrap();
+        device.disable_rx_pipe(Pipe::P2).unwrap();
+        assert_eq!(radio.register(RegisterAddress::EnRxaddr as u8) & 0b100, 0);
+        // Back in RX mode after each write
+        assert_eq!(device.mode(), Mode::Rx);
+        assert_eq!(radio.register(RegisterAddress::Config as u8) & 0b11, 0b11);
+    }
+
+    #[test]
+    fn short_transfer_is_detected() {
+        let ether = Ether::new();
+        let radio = MockRadio::new(&ether);
+        let mut device = radio.device().unwrap();
+
+        radio.short_transfers(1);
+        let result = device.read_register::<RfCh>();
+        assert!(matches!(result, Err(Error::ShortTransfer { expected: 2, got: 1 })));
+    }
+
+    #[test]
+    fn short_transfer_is_retried() {
+        let ether = Ether::new();
+        let radio = MockRadio::new(&ether);
+        let mut device = radio.device().unwrap().with_spi_retries(1);
+        device.set_rf_channel(42).unwrap();
+
+        radio.short_transfers(1);
+        let (_, rf_ch) = device.read_register::<RfCh>().unwrap();
+        assert_eq!(rf_ch.rf_ch(), 42);
+    }
+}
@@ -0,0 +1,511 @@
+//! Simulated nRF24L01+ for host-side testing
+//!
+//! Requires the `mock` feature, which pulls in `std`.
+//!
+//! A [`MockRadio`] simulates the chip behind the SPI bus: the register map, the
+//! three-deep RX/TX FIFOs, the `STATUS` semantics and Enhanced ShockBurst auto-ack.
+//! The regular driver runs on top of it, so a [`MockDevice`] implements `Device`,
+//! `Rx`, `Tx`, `ChangeModes` and `NRF24L01Configuration` exactly like the real thing.
+//! Radios attached to the same [`Ether`] reach each other when they are on the same
+//! channel, data rate and address.
+//!
+//! Transmission is instantaneous: a packet is sent as soon as the transmitting radio
+//! has CE high, and the receiving radio must already be in RX mode to catch it.
+//!
+//! ```
+//! use embedded_nrf24l01::mock::{Ether, MockRadio};
+//! use embedded_nrf24l01::{NRF24L01Configuration, Rx, Tx};
+//!
+//! let ether = Ether::new();
+//! let (a, b) = (MockRadio::new(&ether), MockRadio::new(&ether));
+//! let mut tx = a.device().unwrap();
+//! let mut rx = b.device().unwrap();
+//!
+//! rx.set_read_enabled_pipes(&[false, true, false, false, false, false]).unwrap();
+//! rx.set_rx_addrs(1, b"node1").unwrap();
+//! rx.set_pipes_payload_lengths([None; 6]).unwrap();
+//! tx.set_tx_addr(b"node1").unwrap();
+//! // Auto-ack is received on pipe 0
+//! tx.set_rx_addrs(0, b"node1").unwrap();
+//!
+//! assert_eq!(rx.can_read().unwrap(), None);
+//! tx.send(b"hello").unwrap();
+//! assert!(tx.poll_send().unwrap());
+//! assert_eq!(rx.can_read().unwrap(), Some(1));
+//! assert_eq!(&*rx.read().unwrap(), b"hello");
+//! ```
+
+use core::convert::{Infallible, TryFrom};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
+use std::vec::Vec;
+
+use embedded_hal::blocking::spi::Transfer as SpiTransfer;
+use embedded_hal::digital::v2::OutputPin;
+
+use crate::registers::RegisterAddress as Reg;
+use crate::{Error, NRF24L01Config, MAX_ADDR_BYTES, NRF24L01, PIPES_COUNT};
+
+const FIFO_DEPTH: usize = 3;
+
+const RX_DR: u8 = 1 << 6;
+const TX_DS: u8 = 1 << 5;
+const MAX_RT: u8 = 1 << 4;
+
+/// The driver running on a simulated chip
+pub type MockDevice<'a> = NRF24L01<'a, Infallible, MockPin, MockPin, MockSpi>;
+
+/// Shared air between simulated radios
+#[derive(Clone, Default)]
+pub struct Ether {
+    radios: Rc<RefCell<Vec<Weak<RefCell<Chip>>>>>,
+}
+
+impl Ether {
+    /// Create an empty ether
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send whatever the radio has queued, if it is in a state to transmit
+    fn transmit(&self, sender_rc: &Rc<RefCell<Chip>>) {
+        let radios: Vec<Rc<RefCell<Chip>>> = self
+            .radios
+            .borrow()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .filter(|radio| !Rc::ptr_eq(radio, sender_rc))
+            .collect();
+
+        let mut sender = sender_rc.borrow_mut();
+        while sender.transmitting() {
+            let (packet, no_ack) = match sender.tx_fifo.front() {
+                Some(packet) => (packet.data.clone(), packet.no_ack),
+                None => return,
+            };
+            let width = sender.address_width();
+            let tx_addr = sender.tx_addr;
+            let tx_addr = &tx_addr[0..width];
+            let expects_ack = !no_ack && sender.registers[Reg::EnAa as usize] & 1 != 0;
+
+            let dropped = sender.drop_next > 0;
+            if dropped {
+                sender.drop_next -= 1;
+            }
+
+            // `Some(ack_payload)` if a receiver acknowledged the packet
+            let mut ack = None;
+            if !dropped {
+                for radio in &radios {
+                    let mut receiver = radio.borrow_mut();
+                    if !receiver.listening()
+                        || receiver.rf_channel() != sender.rf_channel()
+                        || receiver.air_rate() != sender.air_rate()
+                        || receiver.address_width() != width
+                    {
+                        continue;
+                    }
+                    if let Some(pipe) = receiver.accepting_pipe(tx_addr, packet.len()) {
+                        // A full RX FIFO loses the packet and doesn't acknowledge it
+                        if receiver.receive(pipe, packet.clone())
+                            && receiver.registers[Reg::EnAa as usize] & (1 << pipe) != 0
+                        {
+                            ack = Some(receiver.take_ack_payload(pipe));
+                        }
+                    }
+                }
+            }
+
+            let mut observe = sender.registers[Reg::ObserveTx as usize];
+            if expects_ack {
+                // The ACK is received on pipe 0, so it must listen on TX_ADDR
+                let ack_heard = &sender.rx_addr_p0[0..width] == tx_addr;
+                match ack {
+                    Some(ack_payload) if ack_heard => {
+                        if let Some(ack_payload) = ack_payload {
+                            sender.receive(0, ack_payload);
+                        }
+                        observe &= 0xF0;
+                    }
+                    _ => {
+                        let retries = sender.registers[Reg::SetupRetr as usize] & 0x0F;
+                        let lost = (observe >> 4).saturating_add(1).min(15);
+                        sender.registers[Reg::ObserveTx as usize] = (lost << 4) | retries;
+                        sender.registers[Reg::Status as usize] |= MAX_RT;
+                        return;
+                    }
+                }
+            }
+
+            sender.registers[Reg::ObserveTx as usize] = observe;
+            sender.registers[Reg::Status as usize] |= TX_DS;
+            if sender.reuse {
+                // A reused payload is sent once per CE pulse
+                return;
+            }
+            sender.tx_fifo.pop_front();
+        }
+    }
+}
+
+/// Handle to a simulated radio
+pub struct MockRadio {
+    chip: Rc<RefCell<Chip>>,
+    ether: Ether,
+}
+
+impl MockRadio {
+    /// Create a radio in its power-on reset state, attached to `ether`
+    pub fn new(ether: &Ether) -> Self {
+        let chip = Rc::new(RefCell::new(Chip::new()));
+        ether.radios.borrow_mut().push(Rc::downgrade(&chip));
+        MockRadio {
+            chip,
+            ether: ether.clone(),
+        }
+    }
+
+    /// The CE pin, CSN pin and SPI bus of the radio
+    pub fn parts(&self) -> (MockPin, MockPin, MockSpi) {
+        let ce = MockPin {
+            chip: self.chip.clone(),
+            ether: self.ether.clone(),
+            is_ce: true,
+        };
+        let csn = MockPin {
+            chip: self.chip.clone(),
+            ether: self.ether.clone(),
+            is_ce: false,
+        };
+        let spi = MockSpi {
+            chip: self.chip.clone(),
+            ether: self.ether.clone(),
+        };
+        (ce, csn, spi)
+    }
+
+    /// Construct the driver with default configuration
+    pub fn device(&self) -> Result<MockDevice<'static>, Error<Infallible>> {
+        let (ce, csn, spi) = self.parts();
+        NRF24L01::new(ce, csn, spi)
+    }
+
+    /// Construct the driver with the given configuration
+    pub fn device_with_config<'a>(&self, config: NRF24L01Config<'a>) -> Result<MockDevice<'a>, Error<Infallible>> {
+        let (ce, csn, spi) = self.parts();
+        NRF24L01::new_with_config(ce, csn, spi, config)
+    }
+
+    /// Lose the next `count` packets sent by this radio on air
+    ///
+    /// With auto-ack enabled the lost packets end in `MAX_RT`.
+    pub fn drop_next(&self, count: usize) {
+        self.chip.borrow_mut().drop_next = count;
+    }
+
+    /// Put a packet straight into the RX FIFO of this radio, as if received on `pipe`
+    ///
+    /// Nothing checks `data`, so a packet longer than 32 bytes stands in for one whose
+    /// width got corrupted.
+    pub fn inject_rx(&self, pipe: u8, data: &[u8]) {
+        self.chip.borrow_mut().receive(pipe, data.to_vec());
+    }
+
+    /// Cut the next `count` SPI transfers of this radio short by one byte
+    ///
+    /// Like a faulty HAL or DMA, the chip doesn't see these transfers and the buffer is
+    /// handed back unchanged.
+    pub fn short_transfers(&self, count: usize) {
+        self.chip.borrow_mut().short_transfers = count;
+    }
+
+    /// Set the state of the received power detector
+    pub fn set_carrier(&self, present: bool) {
+        self.chip.borrow_mut().carrier = present;
+    }
+
+    /// Peek at the first byte of a register without going through the driver
+    pub fn register(&self, addr: u8) -> u8 {
+        self.chip.borrow().read_byte(addr, 0)
+    }
+}
+
+/// Simulated CE or CSN pin of a [`MockRadio`]
+pub struct MockPin {
+    chip: Rc<RefCell<Chip>>,
+    ether: Ether,
+    is_ce: bool,
+}
+
+impl OutputPin for MockPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        if self.is_ce {
+            self.chip.borrow_mut().ce = false;
+        }
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        if self.is_ce {
+            self.chip.borrow_mut().ce = true;
+            self.ether.transmit(&self.chip);
+        }
+        Ok(())
+    }
+}
+
+/// Simulated SPI bus of a [`MockRadio`]
+pub struct MockSpi {
+    chip: Rc<RefCell<Chip>>,
+    ether: Ether,
+}
+
+impl SpiTransfer<u8> for MockSpi {
+    type Error = Infallible;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        {
+            let mut chip = self.chip.borrow_mut();
+            if chip.short_transfers > 0 {
+                chip.short_transfers -= 1;
+                let len = words.len().saturating_sub(1);
+                return Ok(&words[..len]);
+            }
+        }
+        self.chip.borrow_mut().transfer(words);
+        self.ether.transmit(&self.chip);
+        Ok(words)
+    }
+}
+
+struct TxPacket {
+    data: Vec<u8>,
+    no_ack: bool,
+    /// Set for ACK payloads staged with `W_ACK_PAYLOAD`
+    ack_pipe: Option<u8>,
+}
+
+struct Chip {
+    registers: [u8; 0x20],
+    rx_addr_p0: [u8; MAX_ADDR_BYTES],
+    rx_addr_p1: [u8; MAX_ADDR_BYTES],
+    tx_addr: [u8; MAX_ADDR_BYTES],
+    tx_fifo: VecDeque<TxPacket>,
+    rx_fifo: VecDeque<(u8, Vec<u8>)>,
+    reuse: bool,
+    ce: bool,
+    carrier: bool,
+    drop_next: usize,
+    short_transfers: usize,
+}
+
+impl Chip {
+    fn new() -> Self {
+        let mut registers = [0; 0x20];
+        registers[Reg::Config as usize] = 0x08;
+        registers[Reg::EnAa as usize] = 0x3F;
+        registers[Reg::EnRxaddr as usize] = 0x03;
+        registers[Reg::SetupAw as usize] = 0x03;
+        registers[Reg::SetupRetr as usize] = 0x03;
+        registers[Reg::RfCh as usize] = 0x02;
+        registers[Reg::RfSetup as usize] = 0x0E;
+        registers[Reg::RxAddrP2 as usize] = 0xC3;
+        registers[Reg::RxAddrP3 as usize] = 0xC4;
+        registers[Reg::RxAddrP4 as usize] = 0xC5;
+        registers[Reg::RxAddrP5 as usize] = 0xC6;
+        Chip {
+            registers,
+            rx_addr_p0: [0xE7; MAX_ADDR_BYTES],
+            rx_addr_p1: [0xC2; MAX_ADDR_BYTES],
+            tx_addr: [0xE7; MAX_ADDR_BYTES],
+            tx_fifo: VecDeque::new(),
+            rx_fifo: VecDeque::new(),
+            reuse: false,
+            ce: false,
+            carrier: false,
+            drop_next: 0,
+            short_transfers: 0,
+        }
+    }
+
+    fn status(&self) -> u8 {
+        let rx_p_no = self.rx_fifo.front().map_or(0b111, |(pipe, _)| *pipe);
+        let tx_full = self.tx_fifo.len() >= FIFO_DEPTH;
+        (self.registers[Reg::Status as usize] & (RX_DR | TX_DS | MAX_RT)) | (rx_p_no << 1) | tx_full as u8
+    }
+
+    fn fifo_status(&self) -> u8 {
+        ((self.reuse as u8) << 6)
+            | (((self.tx_fifo.len() >= FIFO_DEPTH) as u8) << 5)
+            | ((self.tx_fifo.is_empty() as u8) << 4)
+            | (((self.rx_fifo.len() >= FIFO_DEPTH) as u8) << 1)
+            | self.rx_fifo.is_empty() as u8
+    }
+
+    fn transfer(&mut self, buf: &mut [u8]) {
+        // STATUS is shifted out while the command byte is shifted in
+        let status = self.status();
+        let command = buf[0];
+        match command {
+            0x00..=0x1F => {
+                for (i, byte) in buf[1..].iter_mut().enumerate() {
+                    *byte = self.read_byte(command, i);
+                }
+            }
+            0x20..=0x3F => self.write(command & 0x1F, &buf[1..]),
+            // R_RX_PL_WID
+            0x60 => {
+                let width = self.rx_fifo.front().map_or(0, |(_, data)| data.len());
+                if let Some(byte) = buf.get_mut(1) {
+                    *byte = width as u8;
+                }
+            }
+            // R_RX_PAYLOAD
+            0x61 => {
+                let data = self.rx_fifo.pop_front().map(|(_, data)| data).unwrap_or_default();
+                for (i, byte) in buf[1..].iter_mut().enumerate() {
+                    *byte = data.get(i).copied().unwrap_or(0);
+                }
+            }
+            // W_TX_PAYLOAD and W_TX_PAYLOAD_NOACK
+            0xA0 | 0xB0 => self.push_tx(&buf[1..], command == 0xB0, None),
+            // W_ACK_PAYLOAD
+            0xA8..=0xAD => self.push_tx(&buf[1..], false, Some(command & 0b111)),
+            // FLUSH_TX
+            0xE1 => {
+                self.tx_fifo.clear();
+                self.reuse = false;
+            }
+            // FLUSH_RX
+            0xE2 => self.rx_fifo.clear(),
+            // REUSE_TX_PL
+            0xE3 => self.reuse = true,
+            // NOP and anything undefined
+            _ => {}
+        }
+        buf[0] = status;
+    }
+
+    fn read_byte(&self, addr: u8, index: usize) -> u8 {
+        match Reg::try_from(addr) {
+            Ok(Reg::RxAddrP0) => self.rx_addr_p0.get(index).copied().unwrap_or(0),
+            Ok(Reg::RxAddrP1) => self.rx_addr_p1.get(index).copied().unwrap_or(0),
+            Ok(Reg::TxAddr) => self.tx_addr.get(index).copied().unwrap_or(0),
+            _ if index > 0 => 0,
+            Ok(Reg::Status) => self.status(),
+            Ok(Reg::FifoStatus) => self.fifo_status(),
+            Ok(Reg::Rpd) => self.carrier as u8,
+            _ => self.registers[addr as usize],
+        }
+    }
+
+    fn write(&mut self, addr: u8, data: &[u8]) {
+        let value = match data.first() {
+            Some(value) => *value,
+            None => return,
+        };
+        match Reg::try_from(addr) {
+            Ok(Reg::Status) => self.registers[Reg::Status as usize] &= !(value & (RX_DR | TX_DS | MAX_RT)),
+            Ok(Reg::RxAddrP0) => copy_addr(&mut self.rx_addr_p0, data),
+            Ok(Reg::RxAddrP1) => copy_addr(&mut self.rx_addr_p1, data),
+            Ok(Reg::TxAddr) => copy_addr(&mut self.tx_addr, data),
+            // Writing RF_CH resets the lost packet counter
+            Ok(Reg::RfCh) => {
+                self.registers[Reg::RfCh as usize] = value;
+                self.registers[Reg::ObserveTx as usize] &= 0x0F;
+            }
+            Ok(Reg::ObserveTx | Reg::Rpd | Reg::FifoStatus) => {}
+            _ => self.registers[addr as usize] = value,
+        }
+    }
+
+    fn push_tx(&mut self, data: &[u8], no_ack: bool, ack_pipe: Option<u8>) {
+        if self.tx_fifo.len() < FIFO_DEPTH {
+            self.tx_fifo.push_back(TxPacket {
+                data: data.to_vec(),
+                no_ack,
+                ack_pipe,
+            });
+        }
+    }
+
+    fn powered(&self) -> bool {
+        self.registers[Reg::Config as usize] & 0b10 != 0
+    }
+
+    fn prim_rx(&self) -> bool {
+        self.registers[Reg::Config as usize] & 0b01 != 0
+    }
+
+    fn listening(&self) -> bool {
+        self.powered() && self.prim_rx() && self.ce
+    }
+
+    fn transmitting(&self) -> bool {
+        self.powered()
+            && !self.prim_rx()
+            && self.ce
+            && self.registers[Reg::Status as usize] & MAX_RT == 0
+            && self.tx_fifo.front().is_some_and(|packet| packet.ack_pipe.is_none())
+    }
+
+    fn rf_channel(&self) -> u8 {
+        self.registers[Reg::RfCh as usize] & 0x7F
+    }
+
+    fn air_rate(&self) -> u8 {
+        self.registers[Reg::RfSetup as usize] & 0b0010_1000
+    }
+
+    fn address_width(&self) -> usize {
+        (self.registers[Reg::SetupAw as usize] & 0b11).max(1) as usize + 2
+    }
+
+    fn pipe_address(&self, pipe: u8) -> [u8; MAX_ADDR_BYTES] {
+        match pipe {
+            0 => self.rx_addr_p0,
+            1 => self.rx_addr_p1,
+            _ => {
+                // Pipes 2-5 share all but the LSB with pipe 1
+                let mut addr = self.rx_addr_p1;
+                addr[0] = self.registers[(Reg::RxAddrP0 as u8 + pipe) as usize];
+                addr
+            }
+        }
+    }
+
+    fn accepting_pipe(&self, addr: &[u8], len: usize) -> Option<u8> {
+        let dynamic_enabled = self.registers[Reg::Feature as usize] & 0b100 != 0;
+        (0..PIPES_COUNT as u8).find(|pipe| {
+            let enabled = self.registers[Reg::EnRxaddr as usize] & (1 << pipe) != 0;
+            let dynamic = dynamic_enabled && self.registers[Reg::Dynpd as usize] & (1 << pipe) != 0;
+            let width = self.registers[(Reg::RxPwP0 as u8 + pipe) as usize] as usize;
+            enabled && &self.pipe_address(*pipe)[0..addr.len()] == addr && (dynamic || width == len)
+        })
+    }
+
+    fn receive(&mut self, pipe: u8, data: Vec<u8>) -> bool {
+        if self.rx_fifo.len() >= FIFO_DEPTH {
+            return false;
+        }
+        self.rx_fifo.push_back((pipe, data));
+        self.registers[Reg::Status as usize] |= RX_DR;
+        true
+    }
+
+    fn take_ack_payload(&mut self, pipe: u8) -> Option<Vec<u8>> {
+        if self.registers[Reg::Feature as usize] & 0b10 == 0 {
+            return None;
+        }
+        let index = self.tx_fifo.iter().position(|packet| packet.ack_pipe == Some(pipe))?;
+        self.tx_fifo.remove(index).map(|packet| packet.data)
+    }
+}
+
+fn copy_addr(addr: &mut [u8; MAX_ADDR_BYTES], data: &[u8]) {
+    let len = data.len().min(MAX_ADDR_BYTES);
+    addr[0..len].copy_from_slice(&data[0..len]);
+}
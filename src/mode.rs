@@ -1,5 +1,7 @@
+use embedded_hal::blocking::delay::DelayUs;
+
 /// Mode for the nRF24L01+ Device
-#[derive(PartialEq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Mode {
     /// Standby Mode (Standby-I Mode in the Datasheet).  This mode is meant
     /// to ensure low power usage when there is no data being sent or received.
@@ -7,6 +9,9 @@ pub enum Mode {
     /// Power Down Mode.  This mode is used for the nRF24L01 to consumer minimal
     /// current.  The register values of the device are maintained, but switching
     /// to Standby, Rx, and Tx takes significantly longer
+    ///
+    /// In this state `CONFIG.PWR_UP` is 0 and CE is low. The crystal oscillator is
+    /// stopped, but SPI stays active and the FIFOs keep their content.
     PowerDown,
     /// Sets the Device as a Receiver.  In this mode the nRF24L01 device will
     /// actively receive packets and insert them into the RX FIFOs slots
@@ -18,6 +23,34 @@ pub enum Mode {
     Tx,
 }
 
+/// Power state to put the nRF24L01+ Device in, for use with
+/// [`set_power_state()`](trait.ChangeModes.html#method.set_power_state)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PowerState {
+    /// Power Down mode
+    PowerDown,
+    /// Standby-I mode
+    Standby,
+    /// RX mode
+    Rx,
+    /// TX mode
+    Tx,
+}
+
+impl PowerState {
+    /// Typical current draw in this state in μA, as given by the nRF24L01+ datasheet.
+    ///
+    /// RX is given for 2 Mbps and TX for 0 dBm output power, the worst cases of each.
+    pub fn estimate_current_ua(&self) -> u32 {
+        match self {
+            PowerState::PowerDown => 1,
+            PowerState::Standby => 26,
+            PowerState::Rx => 13_500,
+            PowerState::Tx => 11_300,
+        }
+    }
+}
+
 /// Change the nRF24L01+ Device between different modes defined in the datasheet
 pub trait ChangeModes {
     /// Error for changing the device types (most likely a SPI error)
@@ -37,4 +70,70 @@ pub trait ChangeModes {
     /// Converts the device into TX mode (and Standby-II if no data is in
     /// TX FIFO) as defined in the Mode enum and the datasheet
     fn to_tx(&mut self) -> Result<(), Self::Error>;
+
+    /// Converts the device into RX mode and keeps it there, with CE held high
+    ///
+    /// Every CE drop costs the 130μs RX settling time, during which packets are missed.
+    /// While pinned, anything that would leave RX mode fails instead, including the
+    /// configuration setters that briefly switch to Standby and all sending. Call
+    /// [`unpin_rx()`](#tymethod.unpin_rx) to allow it again. The radio stays in active RX
+    /// all the time, which draws somewhat more current than dropping to Standby between
+    /// reads.
+    fn pin_rx(&mut self) -> Result<(), Self::Error>;
+
+    /// Allows leaving RX mode again after [`pin_rx()`](#tymethod.pin_rx), staying in RX
+    fn unpin_rx(&mut self);
+
+    /// Reads the live `PWR_UP` bit of the `CONFIG` register
+    fn is_powered_up(&mut self) -> Result<bool, Self::Error>;
+
+    /// Powers down, stopping the crystal oscillator
+    ///
+    /// Same as [`to_power_down()`](#tymethod.to_power_down), pair it with
+    /// [`wake()`](#tymethod.wake).
+    fn deep_sleep(&mut self) -> Result<(), Self::Error>;
+
+    /// Powers up into Standby-I, waiting for the oscillator to start up (`Tpd2stby`)
+    ///
+    /// Unlike [`to_standby()`](#tymethod.to_standby) the device is ready for RX or TX
+    /// once this returns.
+    fn wake(&mut self, delay: &mut impl DelayUs<u32>) -> Result<(), Self::Error>;
+
+    /// Waits out the settling time of the last mode transition
+    ///
+    /// Powering up needs `Tpd2stby` for the oscillator to start, and entering RX mode needs
+    /// `Tstby2a` before packets are received. The driver records the pending delay and this
+    /// waits for it, without deducting time that has already passed. Only a single chain of
+    /// Power Down to Standby to RX adds up; returning to Standby drops what was pending, so
+    /// repeatedly leaving and re-entering RX (e.g. for every setter) doesn't pile up. Call it
+    /// before the first `send()` after waking. Returns immediately if nothing is pending.
+    fn wait_ready(&mut self, delay: &mut impl DelayUs<u32>) -> Result<(), Self::Error>;
+
+    /// Reads the live `(PWR_UP, PRIM_RX)` bits of the `CONFIG` register
+    fn read_power_state(&mut self) -> Result<(bool, bool), Self::Error>;
+
+    /// Corrects the tracked mode to match the hardware
+    ///
+    /// Mode tracking is done in software, so if something external changes `CONFIG` the
+    /// tracked mode diverges from reality. This reads `CONFIG` and combines it with the CE
+    /// state to determine the actual mode.
+    fn resync_mode(&mut self) -> Result<(), Self::Error>;
+
+    /// Converts the device into RX mode after flushing the TX FIFO and clearing all
+    /// interrupts, so no stale state from a previous TX session is left behind
+    fn to_rx_clean(&mut self) -> Result<(), Self::Error>;
+
+    /// Converts the device into TX mode after flushing the RX FIFO and clearing all
+    /// interrupts, so no stale state from a previous RX session is left behind
+    fn to_tx_clean(&mut self) -> Result<(), Self::Error>;
+
+    /// Converts the device into the given power state
+    fn set_power_state(&mut self, state: PowerState) -> Result<(), Self::Error> {
+        match state {
+            PowerState::PowerDown => self.to_power_down(),
+            PowerState::Standby => self.to_standby(),
+            PowerState::Rx => self.to_rx(),
+            PowerState::Tx => self.to_tx(),
+        }
+    }
 }
@@ -0,0 +1,10 @@
+/// A free-running microsecond clock
+///
+/// embedded-hal 0.2 has no notion of time, so methods that measure durations take one of
+/// these. Implement it on top of a hardware timer or cycle counter.
+pub trait Monotonic {
+    /// Current time in microseconds
+    ///
+    /// The value may wrap around, durations are computed with wrapping arithmetic.
+    fn now_us(&mut self) -> u32;
+}
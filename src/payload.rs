@@ -1,23 +1,37 @@
+use core::fmt;
+use core::fmt::Debug;
 use core::ops::Deref;
 
-/// Represents a received packet. Stores 32 bytes and the actual length.
+use crate::{Error, MAX_PAYLOAD_BYTES};
+
+/// Represents a received packet. Stores [`MAX_PAYLOAD_BYTES`] bytes and the actual length.
 ///
 /// Use [`as_ref()`](#method.as_ref) or [`Deref`](#impl-Deref) to
 /// obtain a slice of the content.
+// Bytes beyond `len` are always zero, so the derived comparison only depends on the content
+#[derive(PartialEq, Eq, Clone)]
 pub struct Payload {
-    data: [u8; 32],
+    data: [u8; MAX_PAYLOAD_BYTES],
     len: usize,
 }
 
 impl Payload {
-    /// Copy a slice
+    /// Copy a slice, truncating it to [`MAX_PAYLOAD_BYTES`]
     pub fn new(source: &[u8]) -> Self {
-        let mut data = [0; 32];
+        let mut data = [0; MAX_PAYLOAD_BYTES];
         let len = source.len().min(data.len());
         data[0..len].copy_from_slice(&source[0..len]);
         Payload { data, len }
     }
 
+    /// Copy a slice, refusing one longer than [`MAX_PAYLOAD_BYTES`]
+    pub fn from_slice<SPIE: Debug>(source: &[u8]) -> Result<Self, Error<SPIE>> {
+        if source.len() > MAX_PAYLOAD_BYTES {
+            return Err(Error::PayloadTooLong(source.len()));
+        }
+        Ok(Payload::new(source))
+    }
+
     /// Read length
     pub fn len(&self) -> usize {
         self.len
@@ -27,6 +41,26 @@ impl Payload {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Maximum length a payload can hold
+    pub fn capacity(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Take over the content of a `heapless::Vec`
+    #[cfg(feature = "heapless")]
+    pub fn from_heapless(v: heapless::Vec<u8, 32>) -> Self {
+        Payload::new(&v)
+    }
+
+    /// Convert into a `heapless::Vec`
+    #[cfg(feature = "heapless")]
+    pub fn into_heapless(self) -> heapless::Vec<u8, 32> {
+        let mut v = heapless::Vec::new();
+        // Cannot fail, both hold at most 32 bytes
+        let _ = v.extend_from_slice(self.as_ref());
+        v
+    }
 }
 
 impl AsRef<[u8]> for Payload {
@@ -35,6 +69,12 @@ impl AsRef<[u8]> for Payload {
     }
 }
 
+impl fmt::Debug for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Payload").field(&self.as_ref()).finish()
+    }
+}
+
 impl Deref for Payload {
     type Target = [u8];
     fn deref(&self) -> &[u8] {
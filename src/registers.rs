@@ -1,7 +1,113 @@
 #![allow(unused)]
 
+use crate::config::{DataRate, PALevel};
 use crate::{MAX_ADDR_BYTES, MIN_ADDR_BYTES, PIPES_COUNT};
 
+/// Addresses of the registers in the register map
+///
+/// Convert with `as u8` or `u8::from()`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum RegisterAddress {
+    /// `CONFIG`
+    Config = 0x00,
+    /// `EN_AA`
+    EnAa = 0x01,
+    /// `EN_RXADDR`
+    EnRxaddr = 0x02,
+    /// `SETUP_AW`
+    SetupAw = 0x03,
+    /// `SETUP_RETR`
+    SetupRetr = 0x04,
+    /// `RF_CH`
+    RfCh = 0x05,
+    /// `RF_SETUP`
+    RfSetup = 0x06,
+    /// `STATUS`
+    Status = 0x07,
+    /// `OBSERVE_TX`
+    ObserveTx = 0x08,
+    /// `RPD` (`CD` on the nRF24L01)
+    Rpd = 0x09,
+    /// `RX_ADDR_P0`
+    RxAddrP0 = 0x0A,
+    /// `RX_ADDR_P1`
+    RxAddrP1 = 0x0B,
+    /// `RX_ADDR_P2`
+    RxAddrP2 = 0x0C,
+    /// `RX_ADDR_P3`
+    RxAddrP3 = 0x0D,
+    /// `RX_ADDR_P4`
+    RxAddrP4 = 0x0E,
+    /// `RX_ADDR_P5`
+    RxAddrP5 = 0x0F,
+    /// `TX_ADDR`
+    TxAddr = 0x10,
+    /// `RX_PW_P0`
+    RxPwP0 = 0x11,
+    /// `RX_PW_P1`
+    RxPwP1 = 0x12,
+    /// `RX_PW_P2`
+    RxPwP2 = 0x13,
+    /// `RX_PW_P3`
+    RxPwP3 = 0x14,
+    /// `RX_PW_P4`
+    RxPwP4 = 0x15,
+    /// `RX_PW_P5`
+    RxPwP5 = 0x16,
+    /// `FIFO_STATUS`
+    FifoStatus = 0x17,
+    /// `DYNPD`
+    Dynpd = 0x1C,
+    /// `FEATURE`
+    Feature = 0x1D,
+}
+
+impl From<RegisterAddress> for u8 {
+    fn from(addr: RegisterAddress) -> u8 {
+        addr as u8
+    }
+}
+
+impl core::convert::TryFrom<u8> for RegisterAddress {
+    type Error = u8;
+
+    /// Fails with the address itself for the reserved addresses `0x18..=0x1B` and beyond
+    fn try_from(addr: u8) -> Result<Self, u8> {
+        use RegisterAddress::*;
+
+        Ok(match addr {
+            0x00 => Config,
+            0x01 => EnAa,
+            0x02 => EnRxaddr,
+            0x03 => SetupAw,
+            0x04 => SetupRetr,
+            0x05 => RfCh,
+            0x06 => RfSetup,
+            0x07 => Status,
+            0x08 => ObserveTx,
+            0x09 => Rpd,
+            0x0A => RxAddrP0,
+            0x0B => RxAddrP1,
+            0x0C => RxAddrP2,
+            0x0D => RxAddrP3,
+            0x0E => RxAddrP4,
+            0x0F => RxAddrP5,
+            0x10 => TxAddr,
+            0x11 => RxPwP0,
+            0x12 => RxPwP1,
+            0x13 => RxPwP2,
+            0x14 => RxPwP3,
+            0x15 => RxPwP4,
+            0x16 => RxPwP5,
+            0x17 => FifoStatus,
+            0x1C => Dynpd,
+            0x1D => Feature,
+            _ => return Err(addr),
+        })
+    }
+}
+
 pub trait Register {
     /// Address in the register map
     fn addr() -> u8;
@@ -75,6 +181,10 @@ macro_rules! def_address_register {
             pub fn new(buf: &[u8]) -> Self {
                 Self::decode(buf)
             }
+
+            pub fn as_bytes(&self) -> &[u8] {
+                &self.addr[0..self.len.into()]
+            }
         }
 
         impl Register for $name {
@@ -172,18 +282,18 @@ bitfield! {
     /// * `0`: PTX
     pub prim_rx, set_prim_rx: 0;
 }
-impl_register!(Config, 0x00);
+impl_register!(Config, RegisterAddress::Config as u8);
 
 /// Enable Auto Acknowledgment
 #[derive(Debug)]
 pub struct EnAa(pub u8);
-impl_register!(EnAa, 0x01);
+impl_register!(EnAa, RegisterAddress::EnAa as u8);
 def_pipes_accessors!(EnAa, 0b0011_1111, enaa_p, set_enaa_p);
 
 /// Enabled RX Addresses
 #[derive(Debug)]
 pub struct EnRxaddr(u8);
-impl_register!(EnRxaddr, 0x02);
+impl_register!(EnRxaddr, RegisterAddress::EnRxaddr as u8);
 def_pipes_accessors!(EnRxaddr, 0, erx_p, set_erx_p);
 
 bitfield! {
@@ -196,7 +306,7 @@ bitfield! {
     /// * `0b11`: 5 bytes
     pub u8, aw, set_aw: 1, 0;
 }
-impl_register!(SetupAw, 0x03);
+impl_register!(SetupAw, RegisterAddress::SetupAw as u8);
 
 bitfield! {
     /// Setup of Automatic Retransmission
@@ -208,7 +318,7 @@ bitfield! {
     /// Auto Retransmit Count
     pub u8, arc, set_arc: 3, 0;
 }
-impl_register!(SetupRetr, 0x04);
+impl_register!(SetupRetr, RegisterAddress::SetupRetr as u8);
 
 bitfield! {
     /// RF Channel
@@ -218,7 +328,7 @@ bitfield! {
     /// Frequency, that is `2400 + rf_ch` Mhz
     pub u8, rf_ch, set_rf_ch: 6, 0;
 }
-impl_register!(RfCh, 0x05);
+impl_register!(RfCh, RegisterAddress::RfCh as u8);
 
 bitfield! {
     /// RF Setup
@@ -235,8 +345,31 @@ bitfield! {
     /// * `10`: -6 dBm
     /// * `11`: 0 dBm
     pub u8, rf_pwr, set_rf_pwr: 2, 1;
+    /// Low noise amplifier gain (`LNA_HCURR`, nRF24L01 only, reserved on the nRF24L01+)
+    pub lna_gain, set_lna_gain: 0;
+}
+impl_register!(RfSetup, RegisterAddress::RfSetup as u8);
+
+impl RfSetup {
+    pub fn set_data_rate(&mut self, rate: DataRate) {
+        let (dr_low, dr_high) = match rate {
+            DataRate::R250Kbps => (true, false),
+            DataRate::R1Mbps => (false, false),
+            DataRate::R2Mbps => (false, true),
+        };
+        self.set_rf_dr_low(dr_low);
+        self.set_rf_dr_high(dr_high);
+    }
+
+    pub fn set_pa_level(&mut self, level: PALevel) {
+        self.set_rf_pwr(match level {
+            PALevel::PA0dBm => 3,
+            PALevel::PA6dBm => 2,
+            PALevel::PA12dBm => 1,
+            PALevel::PA18dBm => 0,
+        });
+    }
 }
-impl_register!(RfSetup, 0x06);
 
 bitfield! {
     /// Status register, always received on MISO while command is sent
@@ -255,7 +388,19 @@ bitfield! {
     /// TX FIFO full flag
     pub tx_full, _: 0;
 }
-impl_register!(Status, 0x07);
+impl_register!(Status, RegisterAddress::Status as u8);
+
+impl From<u8> for Status {
+    fn from(status: u8) -> Self {
+        Status(status)
+    }
+}
+
+impl From<Status> for u8 {
+    fn from(status: Status) -> u8 {
+        status.0
+    }
+}
 
 bitfield! {
     pub struct ObserveTx(u8);
@@ -264,23 +409,23 @@ bitfield! {
     pub u8, plos_cnt, _: 7, 4;
     pub u8, arc_cnt, _: 3, 0;
 }
-impl_register!(ObserveTx, 0x08);
+impl_register!(ObserveTx, RegisterAddress::ObserveTx as u8);
 
 def_simple!(CD);
-impl_register!(CD, 0x09);
+impl_register!(CD, RegisterAddress::Rpd as u8);
 
-def_address_register!(RxAddrP0, 0x0A);
-def_address_register!(RxAddrP1, 0x0B);
+def_address_register!(RxAddrP0, RegisterAddress::RxAddrP0 as u8);
+def_address_register!(RxAddrP1, RegisterAddress::RxAddrP1 as u8);
 def_simple!(RxAddrP2);
-impl_register!(RxAddrP2, 0x0C);
+impl_register!(RxAddrP2, RegisterAddress::RxAddrP2 as u8);
 def_simple!(RxAddrP3);
-impl_register!(RxAddrP3, 0x0D);
+impl_register!(RxAddrP3, RegisterAddress::RxAddrP3 as u8);
 def_simple!(RxAddrP4);
-impl_register!(RxAddrP4, 0x0E);
+impl_register!(RxAddrP4, RegisterAddress::RxAddrP4 as u8);
 def_simple!(RxAddrP5);
-impl_register!(RxAddrP5, 0x0F);
+impl_register!(RxAddrP5, RegisterAddress::RxAddrP5 as u8);
 
-def_address_register!(TxAddr, 0x10);
+def_address_register!(TxAddr, RegisterAddress::TxAddr as u8);
 
 macro_rules! def_rx_pw {
     ($name: ident, $addr: expr) => {
@@ -296,12 +441,12 @@ macro_rules! def_rx_pw {
     };
 }
 
-def_rx_pw!(RxPwP0, 0x11);
-def_rx_pw!(RxPwP1, 0x12);
-def_rx_pw!(RxPwP2, 0x13);
-def_rx_pw!(RxPwP3, 0x14);
-def_rx_pw!(RxPwP4, 0x15);
-def_rx_pw!(RxPwP5, 0x16);
+def_rx_pw!(RxPwP0, RegisterAddress::RxPwP0 as u8);
+def_rx_pw!(RxPwP1, RegisterAddress::RxPwP1 as u8);
+def_rx_pw!(RxPwP2, RegisterAddress::RxPwP2 as u8);
+def_rx_pw!(RxPwP3, RegisterAddress::RxPwP3 as u8);
+def_rx_pw!(RxPwP4, RegisterAddress::RxPwP4 as u8);
+def_rx_pw!(RxPwP5, RegisterAddress::RxPwP5 as u8);
 
 bitfield! {
     /// Status register, always received on MISO while command is sent
@@ -309,6 +454,7 @@ bitfield! {
     pub struct FifoStatus(u8);
     impl Debug;
 
+    /// Reuse of the last TX payload (`REUSE_TX_PL`) is active
     pub tx_reuse, _: 6;
     /// TX FIFO full flag
     pub tx_full, _: 5;
@@ -319,11 +465,11 @@ bitfield! {
     /// RX FIFO empty flag
     pub rx_empty, _: 0;
 }
-impl_register!(FifoStatus, 0x17);
+impl_register!(FifoStatus, RegisterAddress::FifoStatus as u8);
 
 /// Enable Dynamic Payload length
 pub struct Dynpd(pub u8);
-impl_register!(Dynpd, 0x1C);
+impl_register!(Dynpd, RegisterAddress::Dynpd as u8);
 def_pipes_accessors!(Dynpd, 0, dpl_p, set_dpl_p);
 
 bitfield! {
@@ -338,4 +484,4 @@ bitfield! {
     /// Enables the W_TX_PAYLOAD_NOACK command
     pub en_dyn_ack, set_en_dyn_ack: 0;
 }
-impl_register!(Feature, 0x1D);
+impl_register!(Feature, RegisterAddress::Feature as u8);
@@ -1,5 +1,31 @@
+use embedded_hal::blocking::delay::DelayUs;
+
+use crate::config::Pipe;
 use crate::payload::Payload;
 
+/// A packet read by [`receive()`](trait.Rx.html#tymethod.receive), along with what was known
+/// about it at that instant
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ReceivedPacket {
+    /// The pipe the packet was received on
+    pub pipe: u8,
+    /// The packet content
+    pub payload: Payload,
+    /// Whether this read drained the RX FIFO
+    pub fifo_now_empty: bool,
+}
+
+/// Coarse link quality estimate from [`link_quality()`](trait.Rx.html#tymethod.link_quality)
+///
+/// The chip has no RSSI, so this is only an approximation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LinkQuality {
+    /// Percentage (0-100) of samples where `RPD` detected a signal above -64 dBm
+    pub rpd_fraction: u8,
+    /// Retransmits needed by the last transmitted packet (`OBSERVE_TX.ARC_CNT`)
+    pub last_arc: u8,
+}
+
 /// Represents **RX Mode**
 pub trait Rx {
     /// Error from read states (most commonly SPI errors as device modes are switched whenever
@@ -11,14 +37,53 @@ pub trait Rx {
     /// If there is data, we'll get the pipe number of the data
     fn can_read(&mut self) -> Result<Option<u8>, Self::Error>;
 
+    /// Checks whether there is any incoming data to read, without side effects
+    ///
+    /// Unlike [`can_read()`](#tymethod.can_read) this neither clears the interrupt flags
+    /// nor switches to RX mode; it only reads `STATUS` and `FIFO_STATUS` in a single
+    /// transaction. Returns the pipe number of the next packet.
+    fn has_data(&mut self) -> Result<Option<u8>, Self::Error>;
+
     /// Is an in-band RF signal detected?
     ///
     /// The internal carrier detect signal must be high for 40μs
     /// (NRF24L01+) or 128μs (NRF24L01) before the carrier detect
     /// register is set. Note that changing from standby to receive
     /// mode also takes 130μs.
+    ///
+    /// The register is read right away, so the result may be stale or a false negative
+    /// if the device only just entered RX mode. See
+    /// [`has_carrier_settled()`](#tymethod.has_carrier_settled).
     fn has_carrier(&mut self) -> Result<bool, Self::Error>;
 
+    /// Is an in-band RF signal detected, waiting until the reading is valid?
+    ///
+    /// Switches to RX mode, waits out the pending settling time (130μs from standby, see
+    /// [`wait_ready()`](trait.ChangeModes.html#tymethod.wait_ready)) and then the carrier
+    /// detect time of 40μs (NRF24L01+) or 128μs (NRF24L01) before sampling `RPD`.
+    fn has_carrier_settled(&mut self, delay: &mut impl DelayUs<u32>) -> Result<bool, Self::Error>;
+
+    /// Sample the carrier detect (`RPD`) repeatedly on the current channel and return the
+    /// percentage (0-100) of samples where a carrier was present.
+    ///
+    /// Switches to RX mode first (waiting out the RX settling time if needed) and waits the
+    /// carrier detect time before every sample. Useful for listen-before-talk style behavior.
+    fn measure_channel_occupancy(&mut self, samples: u16, delay: &mut impl DelayUs<u32>) -> Result<u8, Self::Error>;
+
+    /// Scan the whole band, dwelling on each of the 126 channels for `dwell_us`, and return
+    /// the normalized (0-255) share of carrier detect hits per channel
+    ///
+    /// The RX settling time is waited out before the first sample on every channel. The prior
+    /// channel and mode are restored afterwards.
+    fn spectrum_scan(&mut self, delay: &mut impl DelayUs<u32>, dwell_us: u32) -> Result<[u8; 126], Self::Error>;
+
+    /// Estimate link quality while a known transmitter is active
+    ///
+    /// Samples `RPD` like [`measure_channel_occupancy()`](#tymethod.measure_channel_occupancy)
+    /// and adds the retransmit count of the last packet this device sent. Useful for
+    /// comparing antenna placements.
+    fn link_quality(&mut self, samples: u16, delay: &mut impl DelayUs<u32>) -> Result<LinkQuality, Self::Error>;
+
     /// Is the RX queue empty?
     fn rx_queue_empty(&mut self) -> Result<bool, Self::Error>;
 
@@ -26,5 +91,107 @@ pub trait Rx {
     fn rx_queue_is_full(&mut self) -> Result<bool, Self::Error>;
 
     /// Read the next received packet
+    ///
+    /// The pipe it arrived on is available from [`last_rx_pipe()`](#tymethod.last_rx_pipe)
+    /// afterwards.
     fn read(&mut self) -> Result<Payload, Self::Error>;
+
+    /// Read the next received packet along with the pipe it arrived on
+    ///
+    /// The pipe is taken from the `STATUS` clocked out while the payload itself is read, so
+    /// it always matches the packet even if more arrive in between. Returns
+    /// `Error::NoPacket` if the RX FIFO is empty, or if the packet had a corrupt width
+    /// (beyond [`MAX_PAYLOAD_BYTES`](crate::MAX_PAYLOAD_BYTES)) and the RX FIFO was flushed.
+    fn read_with_pipe(&mut self) -> Result<(u8, Payload), Self::Error>;
+
+    /// The pipe of the packet last returned by [`read()`](#tymethod.read) or
+    /// [`read_with_pipe()`](#tymethod.read_with_pipe), `None` before the first one
+    fn last_rx_pipe(&self) -> Option<u8>;
+
+    /// Read received packets into `out` until the RX FIFO is empty or `out` is full
+    ///
+    /// Returns the number of packets read. The FIFO holds at most 3 packets. A packet
+    /// reporting a width beyond 32 bytes is corrupt; the RX FIFO is flushed and reading stops.
+    fn read_all(&mut self, out: &mut [Payload]) -> Result<usize, Self::Error>;
+
+    /// How many times the RX FIFO was found full by [`can_read()`](#tymethod.can_read) or
+    /// [`read()`](#tymethod.read)
+    ///
+    /// Once the three-deep RX FIFO is full, further packets are silently lost, so this is a
+    /// (heuristic) hint that packets were dropped. Each episode of a full FIFO counts once.
+    fn rx_overflow_count(&self) -> u32;
+
+    /// Reset the [`rx_overflow_count()`](#tymethod.rx_overflow_count)
+    fn reset_rx_overflow_count(&mut self);
+
+    /// Read the next received packet if there is one, along with its pipe and whether the
+    /// RX FIFO is empty afterwards
+    ///
+    /// Unlike [`can_read()`](#tymethod.can_read) this doesn't touch the interrupt flags. Keep
+    /// calling it until `fifo_now_empty` is set to drain the FIFO without extra
+    /// [`rx_queue_empty()`](#tymethod.rx_queue_empty) calls.
+    ///
+    /// A packet reporting a width beyond [`MAX_PAYLOAD_BYTES`](crate::MAX_PAYLOAD_BYTES) is
+    /// corrupt; the RX FIFO is flushed as the datasheet asks and `None` is returned.
+    fn receive(&mut self) -> Result<Option<ReceivedPacket>, Self::Error>;
+
+    /// Wait up to `timeout_us` for a packet and return it along with its pipe
+    ///
+    /// Switches to RX mode and waits out the pending settling time (see
+    /// [`wait_ready()`](trait.ChangeModes.html#tymethod.wait_ready)) before the first poll,
+    /// which doesn't count towards the timeout. Returns `None` if nothing arrived in time.
+    fn read_timeout(&mut self, delay: &mut impl DelayUs<u32>, timeout_us: u32) -> Result<Option<(u8, Payload)>, Self::Error>;
+
+    /// Listen briefly for a packet, for duty-cycled receivers
+    ///
+    /// Switches to RX mode, waits out the settling time plus the air time of a full-length
+    /// packet at the configured data rate, address width and CRC, and checks the RX FIFO
+    /// once. Returns to Standby afterwards unless RX is pinned with
+    /// [`pin_rx()`](trait.ChangeModes.html#tymethod.pin_rx). Further packets stay in the FIFO.
+    fn poll_once(&mut self, delay: &mut impl DelayUs<u32>) -> Result<Option<(u8, Payload)>, Self::Error>;
+
+    /// Stage ACK payloads for several pipes with `W_ACK_PAYLOAD`, given as `(pipe, data)`
+    ///
+    /// ACK payloads share the three-deep TX FIFO, so at most 3 can be pending; if more are
+    /// given nothing is written, and if the FIFO fills up while writing the remaining ones
+    /// are dropped. Either way `TxFifoFull` is returned. A payload longer than
+    /// [`MAX_PAYLOAD_BYTES`](crate::MAX_PAYLOAD_BYTES) is refused with `PayloadTooLong`
+    /// before anything is written.
+    ///
+    /// Each payload is sent with the ACK of the *next* packet received on its pipe, so it
+    /// must be staged before that packet arrives; a packet that is already being
+    /// acknowledged gets an empty ACK. Several payloads for the same pipe are used in the
+    /// order given. Requires ACK payloads (`FEATURE.EN_ACK_PAY`) and dynamic payload length
+    /// to be enabled.
+    fn set_ack_payloads(&mut self, payloads: &[(Pipe, &[u8])]) -> Result<(), Self::Error>;
+
+    /// Answer a request received on `pipe` with `ack_data`, for a primary receiver (PRX)
+    ///
+    /// Meant to be called after reading a request: the response is staged as an ACK payload
+    /// and goes out with the ACK of the *next* packet received on `pipe`, not retroactively
+    /// with the ACK of the request just read, which has already been sent. The peer thus
+    /// sends a request, then any packet (e.g. the next request) to pick the response up.
+    ///
+    /// Checks that dynamic payloads are enabled for `pipe` and in `FEATURE.EN_DPL`, returning
+    /// `DynamicPayloadRequired` otherwise, and that ACK payloads are enabled, returning
+    /// `AckPayloadRequired` otherwise. Nothing is written in either case. Same limits as
+    /// [`set_ack_payloads()`](#tymethod.set_ack_payloads).
+    fn respond(&mut self, pipe: Pipe, ack_data: &[u8]) -> Result<(), Self::Error>;
+
+    /// Detect and recover from a stuck RX FIFO
+    ///
+    /// The FIFO is considered stuck if it reports a packet while the payload width is beyond
+    /// 32 bytes or `STATUS.RX_P_NO` names no valid pipe. In that case the RX FIFO is flushed,
+    /// `RX_DR` is cleared and `false` is returned. Returns `true` if the FIFO is healthy.
+    /// Meant to be called periodically by long-running receivers.
+    fn rx_health_check(&mut self) -> Result<bool, Self::Error>;
+
+    /// Drain the RX FIFO, tagging every packet with the pipe it arrived on
+    ///
+    /// The chip has no per-pipe view of pending data: all pipes share one three-deep FIFO
+    /// and `STATUS.RX_P_NO` only names the pipe of the packet at its head. This reads the
+    /// packets one by one and returns them in FIFO order. Packets arriving while draining
+    /// beyond the capacity of 3 are left in the FIFO.
+    #[cfg(feature = "heapless")]
+    fn drain_by_pipe(&mut self) -> Result<heapless::Vec<(u8, Payload), 3>, Self::Error>;
 }
@@ -0,0 +1,41 @@
+//! Timing parameters from the datasheet
+//!
+//! All values are in microseconds.
+
+use crate::config::{CrcMode, DataRate};
+
+/// Start-up time of the crystal oscillator when powering up (`Tpd2stby`)
+pub const POWER_DOWN_TO_STANDBY_US: u32 = 1500;
+
+/// Settling time when changing from Standby to RX or TX mode (`Tstby2a`)
+pub const STANDBY_TO_ACTIVE_US: u32 = 130;
+
+/// Time the internal carrier detect signal must be high before the
+/// `RPD` register is set (nRF24L01+)
+#[cfg(feature = "nrf24l01-plus")]
+pub const CARRIER_DETECT_US: u32 = 40;
+
+/// Time the internal carrier detect signal must be high before the
+/// `CD` register is set (nRF24L01)
+#[cfg(not(feature = "nrf24l01-plus"))]
+pub const CARRIER_DETECT_US: u32 = 128;
+
+/// Time a packet occupies the air
+///
+/// Counts the preamble (1 byte), the address, the packet control field (9 bits, only with
+/// dynamic payloads), the payload and the CRC, sent at `data_rate`. Rounds up to whole
+/// microseconds.
+pub fn air_time_us(payload_len: u8, data_rate: DataRate, addr_width: u8, crc: CrcMode, dynamic_payload: bool) -> u32 {
+    let crc_bytes = match crc {
+        CrcMode::Disabled => 0,
+        CrcMode::OneByte => 1,
+        CrcMode::TwoBytes => 2,
+    };
+    let packet_control_bits = if dynamic_payload { 9 } else { 0 };
+    let bits = 8 * (1 + u32::from(addr_width) + u32::from(payload_len) + crc_bytes) + packet_control_bits;
+    match data_rate {
+        DataRate::R250Kbps => bits * 4,
+        DataRate::R1Mbps => bits,
+        DataRate::R2Mbps => bits.div_ceil(2),
+    }
+}
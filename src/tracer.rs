@@ -0,0 +1,11 @@
+/// Hook to observe the raw SPI traffic of the driver
+///
+/// Register one with [`with_tracer()`](struct.NRF24L01.html#method.with_tracer) to log
+/// every command, e.g. over RTT, ITM or a UART, while bringing up a board.
+pub trait Tracer {
+    /// Called after every completed SPI transaction
+    ///
+    /// `opcode` is the command byte that was sent, `buf` holds the bytes received in
+    /// return: `STATUS` followed by the response data, if any.
+    fn on_command(&mut self, opcode: u8, buf: &[u8]);
+}
@@ -1,4 +1,58 @@
+use embedded_hal::blocking::delay::DelayUs;
+
 use crate::registers::ObserveTx;
+use crate::{DataRate, Monotonic, PALevel, Payload};
+
+/// Identifies a packet queued with [`send_tracked()`](trait.Tx.html#tymethod.send_tracked)
+///
+/// Tokens are handed out sequentially and wrap around.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SendToken(pub u8);
+
+/// Outcome of a packet queued with [`send_tracked()`](trait.Tx.html#tymethod.send_tracked)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Completion {
+    /// The packet this outcome belongs to
+    pub token: SendToken,
+    /// Whether the packet was delivered (and acknowledged, if auto-ack is enabled)
+    pub success: bool,
+    /// `ARC_CNT` at the moment the packet completed, if it can be attributed to this packet
+    pub retransmits: Option<u8>,
+}
+
+/// Outcome of a [`throughput_test()`](trait.Tx.html#tymethod.throughput_test)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ThroughputResult {
+    /// Packets queued
+    pub sent: u32,
+    /// Packets delivered
+    pub succeeded: u32,
+    /// Packets lost, including those flushed from the FIFO after a `MAX_RT`
+    pub failed: u32,
+    /// Time from queueing the first packet until the last one completed
+    pub elapsed_us: u32,
+}
+
+impl ThroughputResult {
+    /// Delivered packets per second
+    pub fn packets_per_second(&self) -> u32 {
+        if self.elapsed_us == 0 {
+            return 0;
+        }
+        (u64::from(self.succeeded) * 1_000_000 / u64::from(self.elapsed_us)) as u32
+    }
+}
+
+/// Link quality estimate from [`link_margin()`](trait.Tx.html#tymethod.link_margin)
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LinkMargin {
+    /// Retransmits per packet, averaged over all samples
+    pub average_retransmits: f32,
+    /// Share of delivered packets, `0.0..=1.0`
+    pub success_rate: f32,
+    /// Coarse rating from 0 (no usable link) to 4 (no retransmits needed)
+    pub bars: u8,
+}
 
 /// Represents **TX Mode** and the associated **TX Settling** and
 /// **Standby-II** states
@@ -21,12 +75,135 @@ pub trait Tx {
     /// Is TX FIFO full?
     fn tx_full(&mut self) -> Result<bool, Self::Error>;
 
+    /// Is the last payload being reused?
+    ///
+    /// While active, every CE pulse retransmits the old payload. Flush the TX FIFO
+    /// before sending fresh data.
+    fn is_reusing_tx_payload(&mut self) -> Result<bool, Self::Error>;
+
     /// Does the TX FIFO have space?
     fn can_send(&mut self) -> Result<bool, Self::Error>;
 
     /// Send asynchronously
+    ///
+    /// Returns `Error::TxFifoFull` if the TX FIFO had no space, in which case the packet
+    /// was not queued. A packet longer than [`MAX_PAYLOAD_BYTES`](crate::MAX_PAYLOAD_BYTES)
+    /// is refused with `Error::PayloadTooLong`.
     fn send(&mut self, packet: &[u8]) -> Result<(), Self::Error>;
 
+    /// Send asynchronously, waiting for space in the TX FIFO first
+    ///
+    /// Packets hitting the maximum amount of retries while waiting are flushed like in
+    /// [`wait_empty()`](#tymethod.wait_empty).
+    fn send_blocking(&mut self, packet: &[u8]) -> Result<(), Self::Error>;
+
+    /// Send asynchronously if the channel is clear (listen-before-talk)
+    ///
+    /// Switches to RX mode and samples `RPD` once the settling time has passed (see
+    /// [`has_carrier_settled()`](trait.Rx.html#tymethod.has_carrier_settled)). If no
+    /// carrier is detected it switches to TX mode and sends, otherwise it returns
+    /// `WouldBlock` and nothing is queued.
+    ///
+    /// `RPD` is coarse: it only reports signals above -64 dBm, so weaker traffic on the
+    /// channel goes unnoticed.
+    fn send_if_clear(&mut self, packet: &[u8], delay: &mut impl DelayUs<u32>) -> nb::Result<(), Self::Error>;
+
+    /// Put a packet into the TX FIFO without starting transmission
+    ///
+    /// Switches to TX mode but leaves CE low, so the packet waits until
+    /// [`trigger()`](#tymethod.trigger). Together they allow loading ahead of time and
+    /// transmitting at an exact moment.
+    ///
+    /// Returns `Error::TxFifoFull` like [`send()`](#tymethod.send).
+    fn load_payload(&mut self, packet: &[u8]) -> Result<(), Self::Error>;
+
+    /// Start transmitting the loaded packets by raising CE
+    ///
+    /// CE must stay high for at least 10µs for a transmission to start; it is left high
+    /// until the FIFO is empty and the completion is polled.
+    fn trigger(&mut self);
+
+    /// Send to `addr` once, without changing the configured TX address
+    ///
+    /// `TX_ADDR` and `RX_ADDR_P0` (for the ACK) are pointed at `addr`, the packet is sent
+    /// and the configured addresses are restored. As the address can only be changed
+    /// between transmissions, this blocks until the packet was delivered or dropped, and
+    /// returns whether it was delivered (always `true` without auto-ack). The TX FIFO should
+    /// be empty beforehand, or queued packets go to `addr` as well.
+    fn send_to(&mut self, addr: &[u8], packet: &[u8]) -> Result<bool, Self::Error>;
+
+    /// Send the same packet to each of `addrs` in turn
+    ///
+    /// Works like [`send_to()`](#tymethod.send_to) for every address, blocking until each
+    /// packet was delivered or dropped, and returns whether it was delivered per address.
+    /// The configured addresses are restored at the end. More than 16 addresses return
+    /// `Error::TooManyAddresses` without sending anything.
+    #[cfg(feature = "heapless")]
+    fn multicast(&mut self, addrs: &[&[u8]], packet: &[u8]) -> Result<heapless::Vec<bool, 16>, Self::Error>;
+
+    /// Send a single packet at the given PA level
+    ///
+    /// Blocks until the packet was delivered or dropped and returns whether it was
+    /// delivered. The configured level is restored afterwards; only the power bits of
+    /// `RF_SETUP` are touched.
+    fn send_at_pa(&mut self, packet: &[u8], pa: PALevel) -> Result<bool, Self::Error>;
+
+    /// Send, retrying at higher power levels when the maximum retransmits are reached
+    ///
+    /// Starts at the configured PA level and steps up towards 0 dBm after each `MAX_RT`.
+    /// Blocks until the packet was delivered or failed at 0 dBm. Returns the level it was
+    /// delivered at, or `None` if it failed at every level. The configured level is restored
+    /// afterwards either way.
+    fn send_with_pa_escalation(&mut self, packet: &[u8]) -> Result<Option<PALevel>, Self::Error>;
+
+    /// Send, falling back to more robust data rates when the maximum retransmits are reached
+    ///
+    /// Starts at the configured data rate and steps down (2 Mbps, 1 Mbps, 250 Kbps) after
+    /// each `MAX_RT`. Blocks until the packet was delivered or failed at the lowest rate.
+    /// Returns the last rate tried and whether the packet was delivered. Afterwards the
+    /// configured rate is restored, unless `keep_working_rate` is set and the packet got
+    /// through, in which case the working rate stays.
+    ///
+    /// Both ends must use the same data rate, so this is only useful with a peer that
+    /// follows the same fallback.
+    fn send_adaptive(&mut self, packet: &[u8], keep_working_rate: bool) -> Result<(DataRate, bool), Self::Error>;
+
+    /// Send a packet and measure how long it takes until it is acknowledged
+    ///
+    /// Blocks until `TX_DS` or `MAX_RT`. Returns the elapsed time in microseconds, including
+    /// any retransmits, or `None` if the packet wasn't delivered. The TX FIFO should be empty
+    /// beforehand, or the time of the queued packets is included.
+    fn measure_rtt(&mut self, packet: &[u8], timer: &mut impl Monotonic) -> Result<Option<u32>, Self::Error>;
+
+    /// Send asynchronously, returning a token identifying the packet
+    ///
+    /// Use [`poll_completion()`](#tymethod.poll_completion) to obtain the outcome of each
+    /// tracked packet. Don't mix this with [`send()`](#tymethod.send) while tracked packets
+    /// are pending. [`poll_send()`](#tymethod.poll_send), [`wait_empty()`](#tymethod.wait_empty)
+    /// and [`flush_tx()`](trait.NRF24L01Configuration.html#tymethod.flush_tx) also complete
+    /// the tracked packets, their outcomes are dropped then. The pending count saturates at
+    /// 255 if the outcomes are never collected.
+    fn send_tracked(&mut self, packet: &[u8]) -> Result<SendToken, Self::Error>;
+
+    /// Poll for the outcome of the oldest pending tracked packet
+    ///
+    /// Returns `None` while the oldest packet is still in flight or nothing is pending.
+    /// Outcomes are reported one at a time in the order the packets were queued.
+    ///
+    /// `TX_DS` is a single flag, so attribution is only exact if this is polled at least
+    /// once per packet air time. When completions coalesce, the outcomes are still reported
+    /// in order but `retransmits` is `None` for packets it cannot be attributed to. On
+    /// `MAX_RT` the whole TX FIFO is flushed, so every packet queued behind the failed one
+    /// is reported as failed too.
+    fn poll_completion(&mut self) -> Result<Option<Completion>, Self::Error>;
+
+    /// Send `payload` `count` times as fast as possible and measure the outcome
+    ///
+    /// Keeps the TX FIFO full with [`send_tracked()`](#tymethod.send_tracked) and collects
+    /// the outcomes with [`poll_completion()`](#tymethod.poll_completion), so the same
+    /// caveats apply. No tracked packets should be pending beforehand.
+    fn throughput_test(&mut self, payload: &[u8], count: u32, timer: &mut impl Monotonic) -> Result<ThroughputResult, Self::Error>;
+
     /// Poll completion of one or multiple send operations and check whether transmission was
     /// successful.
     ///
@@ -34,6 +211,30 @@ pub trait Tx {
     /// successful and that it provides an asynchronous interface.
     fn poll_send(&mut self) -> nb::Result<bool, Self::Error>;
 
+    /// Read an ACK payload received in reply to a sent packet
+    ///
+    /// For the PTX side: when the receiver attaches a payload to its ACK, it lands in the RX
+    /// FIFO (on pipe 0) and the chip sets `RX_DR` alongside `TX_DS`. Call this after a
+    /// successful send to fetch it without leaving TX mode. `RX_DR` is cleared once it was
+    /// read. Returns `None` if no ACK payload arrived, or if its width was corrupt (beyond
+    /// [`MAX_PAYLOAD_BYTES`](crate::MAX_PAYLOAD_BYTES)), in which case the RX FIFO is flushed.
+    ///
+    /// As ACK payloads share the RX FIFO with regular packets, only use this on a device
+    /// that isn't also receiving in RX mode.
+    fn read_ack_payload(&mut self) -> Result<Option<Payload>, Self::Error>;
+
+    /// Did an ACK payload arrive for a sent packet?
+    ///
+    /// Checks that `RX_DR` is set and the RX FIFO is not empty, in a single read of
+    /// `FIFO_STATUS` that leaves the mode and the interrupt flags alone. If so, fetch it with
+    /// [`read_ack_payload()`](#tymethod.read_ack_payload).
+    fn ack_payload_available(&mut self) -> Result<bool, Self::Error>;
+
+    /// Outcome most recently returned by [`poll_send()`](#tymethod.poll_send)
+    ///
+    /// `None` until `poll_send()` completes, and again after each new `send()`.
+    fn last_send_succeeded(&self) -> Option<bool>;
+
     /// Clears tx interrupts and disables the device (sets ce to false)
     fn clear_tx_interrupts_and_ce(&mut self) -> nb::Result<(), Self::Error>;
 
@@ -44,7 +245,62 @@ pub trait Tx {
     /// lost.
     fn wait_empty(&mut self) -> Result<(), Self::Error>;
 
+    /// Wait until TX FIFO is empty, giving up after `timeout_us`
+    ///
+    /// Returns `Ok(true)` if the FIFO emptied in time and `Ok(false)` on timeout. On timeout
+    /// the FIFO is left as-is (so the caller can decide whether to flush) and CE is dropped.
+    /// Packets hitting the maximum amount of retries are flushed like in
+    /// [`wait_empty()`](#tymethod.wait_empty).
+    fn wait_empty_timeout(&mut self, delay: &mut impl DelayUs<u32>, timeout_us: u32) -> Result<bool, Self::Error>;
+
     /// Read the `OBSERVE_TX` register
     fn observe(&mut self) -> Result<ObserveTx, Self::Error>;
+
+    /// Read the lost packet count, `OBSERVE_TX.PLOS_CNT`
+    ///
+    /// Counts packets that hit the maximum amount of retransmits. It saturates at 15 and
+    /// does not reset on its own, not even on power down: only writing `RF_CH` clears it,
+    /// see [`reset_lost_packet_counter()`](#tymethod.reset_lost_packet_counter).
+    fn lost_packets_since_reset(&mut self) -> Result<u8, Self::Error>;
+
+    /// Clear `OBSERVE_TX.PLOS_CNT` by writing the current channel to `RF_CH` again
+    fn reset_lost_packet_counter(&mut self) -> Result<(), Self::Error>;
+
+    /// Send a packet, wait for it to complete and read `OBSERVE_TX`
+    ///
+    /// Returns whether the packet was delivered along with the retransmit and lost packet
+    /// counts right after its transmission. The TX FIFO should be empty beforehand, or the
+    /// queued packets are sent first.
+    fn send_and_observe(&mut self, packet: &[u8]) -> Result<(bool, ObserveTx), Self::Error>;
+
+    /// Send a packet, wait for it to complete and count the transmissions it took
+    ///
+    /// Returns whether the packet was delivered and the number of attempts, the initial
+    /// transmission plus `OBSERVE_TX.ARC_CNT` retransmits. Clearing the interrupt flags
+    /// leaves `ARC_CNT` alone, it is only reset once the next packet goes out. Same FIFO
+    /// caveat as [`send_and_observe()`](#tymethod.send_and_observe).
+    fn send_counting(&mut self, packet: &[u8]) -> Result<(bool, u8), Self::Error>;
+
+    /// Send a packet with a single attempt, for data that is worthless once stale
+    ///
+    /// `SETUP_RETR.ARC` is set to 0 for this packet only, so it fails fast instead of
+    /// retransmitting for up to several milliseconds. Blocks until the attempt is done and
+    /// returns whether it was acknowledged (always `true` without auto-ack). The previous
+    /// `SETUP_RETR` is restored even if sending failed. Same FIFO caveat as
+    /// [`send_and_observe()`](#tymethod.send_and_observe).
+    fn send_realtime(&mut self, packet: &[u8]) -> Result<bool, Self::Error>;
+
+    /// Estimate the link margin by sending `payload` `sample_sends` times
+    ///
+    /// Retransmits serve as a proxy for margin: a link that needs none has plenty to spare.
+    /// The TX FIFO is flushed and the TX interrupts are cleared before every sample. The
+    /// bars are
+    ///
+    /// * 0: less than half of the packets delivered
+    /// * 1: less than 90% delivered
+    /// * 2: 2 or more retransmits on average
+    /// * 3: more than 0.5 retransmits on average
+    /// * 4: otherwise
+    fn link_margin(&mut self, sample_sends: u8, payload: &[u8]) -> Result<LinkMargin, Self::Error>;
 }
 